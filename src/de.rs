@@ -1,8 +1,1412 @@
-use serde::Deserialize;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::rc::Rc;
 
-use crate::{Error, Result};
+use serde::de::{self, Deserialize, DeserializeSeed, IntoDeserializer, Visitor};
 
+use crate::env::Environment;
+use crate::escape::unquote;
+use crate::parse::{self, strip_comment, Category, Entry, Node, Position, Settings, Value};
+use crate::resolve::Resolver;
+use crate::scalar::{infer, parse_bool, parse_float, parse_int};
+use crate::types::{self, INFERRED_TOKEN, VARREF_TOKEN};
+use crate::variables::is_name_char;
+use crate::{Error, Location, Result, SyntaxKind};
 
-pub fn from_str<'a, T>(_text: &'a str) -> Result<T> where T: Deserialize<'a> {
-    Err(Error::NotSupported("Deserialization"))
-} 
+
+pub fn from_str<'a, T>(text: &'a str) -> Result<T> where T: Deserialize<'a> {
+    let mut deserializer = Deserializer::from_str(text);
+    T::deserialize(&mut deserializer)
+}
+
+/// Reads hyprlang text
+///
+/// Structs, maps and sequences are read from the categories and `key = value` lines,
+/// everything else (scalars, tupples, enums) treats the whole text as a single value
+///
+/// Enums are tagged by their variant: within a value it is the first element (`Exec, kitty`),
+/// within a category it is the only key (`Exec = kitty`, or `Dwindle { }` for a struct)
+///
+/// `#[serde(flatten)]`, internally tagged and untagged enums read the text without knowing the types,
+/// the types are then inferred from how values are written (see [`Value`](crate::Value)).
+/// Repeated categories are merged there, and only repeated values become sequences.
+/// So there a `String` (or a map of them) fails on a value that looks like a number or a bool,
+/// and a `Vec` fails on a single line. Use [`Inferred`](crate::Inferred) for values of any type
+pub struct Deserializer<'de> {
+    input: &'de str,
+    file: Option<String>,
+    settings: Settings,
+    options: Options,
+    variables: BTreeMap<String, String>,
+    warnings: Vec<Error>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Options {
+    special_category_key: Option<String>,
+    resolve_var_refs: bool,
+    keyword_variants: bool,
+    /// Collected while deserializing, and moved to the [`Deserializer`] afterwards
+    warnings: RefCell<Vec<Error>>,
+}
+
+impl<'de> Deserializer<'de> {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(input: &'de str) -> Self {
+        Deserializer {
+            input,
+            file: None,
+            settings: Settings::default(),
+            options: Options::default(),
+            variables: BTreeMap::new(),
+            warnings: vec![]
+        }
+    }
+
+    /// The name of the file the text was read from, which is part of the [`Location`] of errors
+    ///
+    /// With a [`resolver`](Self::resolver) it is also the path relative includes are resolved against
+    pub fn file_name(mut self, name: &str) -> Self {
+        self.file = Some(name.to_string());
+        self
+    }
+
+    /// Follows `source = path` lines, reading the files through the resolver
+    ///
+    /// Only lines outside of any category are includes, like in hyprlang.
+    /// Within a category, or without a resolver, `source` is read like any other key
+    pub fn resolver(mut self, resolver: impl Resolver + 'static) -> Self {
+        self.settings.resolver = Some(Box::new(resolver));
+        self
+    }
+
+    /// Defines `$name = value` before the text is read, for example `LAPTOP` for `# hyprlang if LAPTOP`
+    ///
+    /// Definitions within the text replace these
+    pub fn variable(mut self, name: &str, value: &str) -> Self {
+        self.settings.variables.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Expands `$name`s the text does not define from the environment, such as `$HOME` or `$XDG_CONFIG_HOME`
+    ///
+    /// Variables defined in the text take precedence. Use [`SystemEnvironment`](crate::SystemEnvironment)
+    /// for the environment of the process, or a map of fixed values
+    pub fn environment(mut self, environment: impl Environment + 'static) -> Self {
+        self.settings.environment = Some(Box::new(environment));
+        self
+    }
+
+    /// `$name`s found neither in the text nor the [`environment`](Self::environment) are kept as they are written,
+    /// instead of failing
+    ///
+    /// This does not need an environment, without one every variable the text does not define is kept
+    pub fn keep_unresolved_environment(mut self) -> Self {
+        self.settings.keep_unresolved = true;
+        self
+    }
+
+    /// A `source =` glob that matches no file becomes a warning, instead of failing
+    pub fn allow_empty_globs(mut self) -> Self {
+        self.settings.allow_empty_globs = true;
+        self
+    }
+
+    /// Problems that did not stop the deserialization, each with its [`Location`]
+    ///
+    /// Within a `# hyprlang noerror true` region lines that can not be parsed (or expanded) become warnings,
+    /// and are skipped. The same goes for keys a struct does not know, and for bools, numbers and strings
+    /// of the wrong type: the earlier assignment of the key is used instead (without one they are still an error)
+    ///
+    /// Only available once something was deserialized, so [`from_str`] has none of them
+    pub fn warnings(&self) -> &[Error] {
+        &self.warnings
+    }
+
+    /// The value of every `$variable` (without the `$`) defined in the text, with all references expanded
+    ///
+    /// Only available once something was deserialized
+    pub fn variables(&self) -> &BTreeMap<String, String> {
+        &self.variables
+    }
+
+    /// [`VarRef`](crate::VarRef)s receive the value of the variable, instead of the reference to it
+    pub fn resolve_var_refs(mut self) -> Self {
+        self.options.resolve_var_refs = true;
+        self
+    }
+
+    /// Enums within the sequence of all lines (such as a `Vec` at the root) take their variant from the keyword of the line
+    ///
+    /// `bind = SUPER, Q, exec, kitty` followed by `submap = resize` are read as `Bind(..)` and `Submap(..)`,
+    /// keeping the order of the lines. See [`Serializer::keyword_variants`](crate::Serializer::keyword_variants)
+    pub fn keyword_variants(mut self) -> Self {
+        self.options.keyword_variants = true;
+        self
+    }
+
+    fn parse(&mut self) -> Result<Category> {
+        let document = parse::parse(self.input, self.file.as_deref(), &self.settings)?;
+        self.variables = document.variables.resolve_all()?;
+        self.warnings = document.warnings;
+        Ok(document.root)
+    }
+
+    /// The field that identifies a keyed special category, such as `name` for `device { name = my-mouse }`
+    ///
+    /// Maps are then read from these repeated categories, keyed by the value of the field.
+    /// `device[my-mouse] { }` is always understood, and can be set per field by renaming it to `device[name]`
+    pub fn special_category_key(mut self, field: &str) -> Self {
+        self.options.special_category_key = Some(field.to_string());
+        self
+    }
+
+    /// The input as a single value, read like the right side of a `key = value`
+    fn value(&mut self) -> Result<Value> {
+        let document = parse::parse_value(self.input, self.file.as_deref(), &self.settings)?;
+        self.variables = document.variables.resolve_all()?;
+        self.warnings = document.warnings;
+
+        let mut values = document.root.entries.into_iter().filter_map(|entry| match entry.node {
+            Node::Value(value) => Some(value),
+            Node::Category(_) => None
+        });
+        let value = values.next().unwrap_or_else(|| Value {
+            text: String::new(),
+            raw: String::new(),
+            position: Position { file: self.file.as_deref().map(Rc::from), ..Position::default() }
+        });
+        if let Some(other) = values.next() {
+            return Err(Error::Syntax(SyntaxKind::Other, "expected a single value, found another line".to_string()).at(|| other.position.location(other.raw.len(), "")));
+        }
+
+        Ok(value)
+    }
+
+    /// Whether the input is a single value rather than a document, having no `key = value` or `category {`
+    fn is_value(&self) -> bool {
+        let mut lines = self.input.lines().map(|line| strip_comment(line).trim());
+        !self.input.trim().is_empty() && !self.input.contains('=') && !lines.any(|line| line.ends_with('{'))
+    }
+}
+
+macro_rules! forward_to_value {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+                ValueDeserializer::new(&self.value()?, &self.options).$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
+    type Error = Error;
+
+    forward_to_value! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_f32 deserialize_f64
+        deserialize_char deserialize_str deserialize_string deserialize_bytes deserialize_byte_buf
+        deserialize_identifier
+    }
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        if self.is_value() {
+            return ValueDeserializer::new(&self.value()?, &self.options).deserialize_any(visitor);
+        }
+
+        let root = self.parse()?;
+        let value = CategoryDeserializer::new(&root.entries, &self.options).deserialize_any(visitor);
+        self.warnings.append(self.options.warnings.get_mut());
+        value
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        if self.input.trim().is_empty() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        if name == INFERRED_TOKEN {
+            ValueDeserializer::new(&self.value()?, &self.options).deserialize_newtype_struct(name, visitor)
+        } else {
+            visitor.visit_newtype_struct(self)
+        }
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        let root = self.parse()?;
+        let value = CategoryDeserializer::new(&root.entries, &self.options).deserialize_seq(visitor);
+        self.warnings.append(self.options.warnings.get_mut());
+        value
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        ValueDeserializer::new(&self.value()?, &self.options).deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(self, name: &'static str, len: usize, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        ValueDeserializer::new(&self.value()?, &self.options).deserialize_tuple_struct(name, len, visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        let root = self.parse()?;
+        let value = CategoryDeserializer::new(&root.entries, &self.options).deserialize_map(visitor);
+        self.warnings.append(self.options.warnings.get_mut());
+        value
+    }
+
+    fn deserialize_struct<V>(self, name: &'static str, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+        where V: Visitor<'de> {
+        let root = self.parse()?;
+        let value = CategoryDeserializer::new(&root.entries, &self.options).deserialize_struct(name, fields, visitor);
+        self.warnings.append(self.options.warnings.get_mut());
+        value
+    }
+
+    /// Text without any `key = value` or `category {` is a single value, such as `Tuple, 1, 2`,
+    /// everything else has the variant as its only key
+    fn deserialize_enum<V>(self, name: &'static str, variants: &'static [&'static str], visitor: V) -> Result<V::Value>
+        where V: Visitor<'de> {
+        if self.is_value() {
+            return ValueDeserializer::new(&self.value()?, &self.options).deserialize_enum(name, variants, visitor);
+        }
+
+        let root = self.parse()?;
+        let value = CategoryDeserializer::new(&root.entries, &self.options).deserialize_enum(name, variants, visitor);
+        self.warnings.append(self.options.warnings.get_mut());
+        value
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_unit()
+    }
+}
+
+/// The content of a category (or the root)
+/// Deserializes as a map/struct of its keys, or as a sequence of all its entries
+struct CategoryDeserializer<'a> {
+    entries: Vec<&'a Entry>,
+    opts: &'a Options,
+    /// The key of a `category[key]`, which is added as the key field if the category does not set it itself
+    injected: Option<(&'a str, &'a str)>,
+}
+
+impl<'a> CategoryDeserializer<'a> {
+    fn new(entries: &'a [Entry], opts: &'a Options) -> Self {
+        CategoryDeserializer { entries: entries.iter().collect(), opts, injected: None }
+    }
+
+    /// Groups the entries by key, in the order the keys first appeared
+    fn fields(&self) -> Vec<(&'a str, FieldValue<'a>)> {
+        let mut fields: Vec<(&'a str, FieldValue<'a>)> = vec![];
+
+        for entry in self.entries.iter().copied() {
+            match fields.iter_mut().find(|(key, _)| *key == entry.key) {
+                Some((_, FieldValue::Field(field))) => field.items.push(entry),
+                _ => fields.push((entry.key.as_str(), FieldValue::Field(FieldDeserializer::new(&entry.key, vec![entry], self.opts)))),
+            }
+        }
+
+        if let Some((field, key)) = self.injected {
+            if !fields.iter().any(|(name, _)| *name == field) {
+                fields.insert(0, (field, FieldValue::Text(key)));
+            }
+        }
+
+        fields
+    }
+
+    fn not_a_value(&self) -> Error {
+        Error::Message("expected a value, found a category".to_string())
+    }
+}
+
+macro_rules! reject_category {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V>(self, _visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+                Err(self.not_a_value())
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for CategoryDeserializer<'_> {
+    type Error = Error;
+
+    reject_category! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_f32 deserialize_f64
+        deserialize_char deserialize_str deserialize_string deserialize_bytes deserialize_byte_buf
+        deserialize_identifier
+    }
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        let keywords = self.opts.keyword_variants;
+        visitor.visit_seq(ItemsAccess { items: self.entries.into_iter(), keywords, opts: self.opts })
+    }
+
+    fn deserialize_tuple<V>(self, _len: usize, _visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        Err(self.not_a_value())
+    }
+
+    fn deserialize_tuple_struct<V>(self, _name: &'static str, _len: usize, _visitor: V) -> Result<V::Value>
+        where V: Visitor<'de> {
+        Err(self.not_a_value())
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_map(CategoryAccess { fields: self.fields().into_iter(), value: None, opts: self.opts })
+    }
+
+    fn deserialize_struct<V>(self, _name: &'static str, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+        where V: Visitor<'de> {
+        let mut groups = self.fields();
+
+        // Keys only found within `noerror` regions are dropped, if the struct does not know them
+        groups.retain(|(key, value)| {
+            let FieldValue::Field(field) = value else {
+                return true;
+            };
+            let known = fields.iter().any(|declared| declared == key || split_special(declared).is_some_and(|(category, _)| category == *key));
+            if known || !field.items.iter().all(|item| item.noerror) {
+                return true;
+            }
+
+            let location = key_location(field.last().entry);
+            self.opts.warnings.borrow_mut().push(Error::Message(format!("unknown key `{key}`")).at(|| location).in_key(key));
+            false
+        });
+
+        // Fields renamed to `device[name]` are read from `device`, as special categories keyed by `name`
+        for declared in fields {
+            let Some((category, key_field)) = split_special(declared) else {
+                continue;
+            };
+
+            for (key, value) in groups.iter_mut() {
+                if let (true, FieldValue::Field(field)) = (*key == category, value) {
+                    *key = declared;
+                    field.key_field = Some(key_field);
+                }
+            }
+        }
+
+        visitor.visit_map(CategoryAccess { fields: groups.into_iter(), value: None, opts: self.opts })
+    }
+
+    /// The variant is the only key within the category, `Tuple = 1, 2` or `Struct { a = 1 }`
+    fn deserialize_enum<V>(self, name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value>
+        where V: Visitor<'de> {
+        let mut fields = self.fields().into_iter().filter_map(|(key, value)| match value {
+            FieldValue::Field(field) => Some((key, field)),
+            FieldValue::Text(_) => None
+        });
+
+        let Some((key, field)) = fields.next() else {
+            return Err(Error::Message(format!("expected a variant of `{name}`, found an empty category")));
+        };
+        if let Some((other, second)) = fields.next() {
+            let msg = format!("expected a single variant of `{name}`, found `{key}` and `{other}`");
+            return Err(Error::Message(msg).at(|| key_location(second.last().entry)));
+        }
+
+        visitor.visit_enum(CategoryEnum { key, field })
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_unit()
+    }
+}
+
+/// `device[name]` is split into `device` and `name`
+fn split_special(key: &str) -> Option<(&str, &str)> {
+    let (category, rest) = key.split_once('[')?;
+    Some((category, rest.strip_suffix(']')?))
+}
+
+fn key_location(entry: &Entry) -> Location {
+    entry.position.location(entry.key.len(), "")
+}
+
+/// Adds the key to the front of the path of the warnings found while reading its value,
+/// as [`Error::in_key`] does for the errors
+fn warnings_in_key<T>(opts: &Options, key: &str, read: impl FnOnce() -> Result<T>) -> Result<T> {
+    let before = opts.warnings.borrow().len();
+    let res = read();
+    for warning in opts.warnings.borrow_mut()[before..].iter_mut() {
+        *warning = std::mem::replace(warning, Error::Message(String::new())).in_key(key);
+    }
+    res
+}
+
+/// The value of a `key = value` line, or the key of a category
+fn entry_location(entry: &Entry) -> Location {
+    match &entry.node {
+        Node::Value(value) => value.position.location(value.raw.len(), ""),
+        Node::Category(_) => key_location(entry)
+    }
+}
+
+enum FieldValue<'a> {
+    Field(FieldDeserializer<'a>),
+    Text(&'a str),
+}
+
+struct CategoryAccess<'a> {
+    fields: std::vec::IntoIter<(&'a str, FieldValue<'a>)>,
+    value: Option<FieldValue<'a>>,
+    opts: &'a Options,
+}
+
+impl<'de> de::MapAccess<'de> for CategoryAccess<'_> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>> where K: DeserializeSeed<'de> {
+        match self.fields.next() {
+            Some((key, value)) => {
+                let entry = match &value {
+                    FieldValue::Field(field) => Some(field.last().entry),
+                    FieldValue::Text(_) => None
+                };
+                self.value = Some(value);
+
+                seed.deserialize(ValueDeserializer::text(key, self.opts))
+                    .map(Some)
+                    .map_err(|e| match entry {
+                        Some(entry) => e.at(|| key_location(entry)).in_key(key),
+                        None => e
+                    })
+            },
+            None => Ok(None)
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value> where V: DeserializeSeed<'de> {
+        match self.value.take() {
+            Some(FieldValue::Field(field)) => {
+                let (key, last, opts) = (field.key, field.last().entry, field.opts);
+                warnings_in_key(opts, key, || seed.deserialize(field)).map_err(|e| e.at(|| entry_location(last)).in_key(key))
+            },
+            Some(FieldValue::Text(text)) => seed.deserialize(ValueDeserializer::text(text, self.opts)),
+            None => Err(Error::UnexpectedSequence("value requested before its key".to_string()))
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.fields.len())
+    }
+}
+
+/// An enum read from a category, the variant is its only key
+struct CategoryEnum<'a> {
+    key: &'a str,
+    field: FieldDeserializer<'a>,
+}
+
+impl<'de> de::EnumAccess<'de> for CategoryEnum<'_> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self)> where V: DeserializeSeed<'de> {
+        let last = self.field.last().entry;
+        let value = seed.deserialize(ValueDeserializer::text(self.key, self.field.opts))
+            .map_err(|e| e.at(|| key_location(last)).in_key(self.key))?;
+        Ok((value, self))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for CategoryEnum<'_> {
+    type Error = Error;
+
+    /// `Unit =` or an empty `Unit { }`
+    fn unit_variant(self) -> Result<()> {
+        let empty = self.field.items.iter().all(|item| match &item.node {
+            Node::Value(value) => value.text.is_empty(),
+            Node::Category(category) => category.entries.is_empty(),
+        });
+
+        if empty {
+            Ok(())
+        } else {
+            let (key, last) = (self.key, self.field.last().entry);
+            Err(Error::Message(format!("the variant `{key}` has no data")).at(|| entry_location(last)).in_key(key))
+        }
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value> where T: DeserializeSeed<'de> {
+        let (key, last, opts) = (self.key, self.field.last().entry, self.field.opts);
+        warnings_in_key(opts, key, || seed.deserialize(self.field)).map_err(|e| e.at(|| entry_location(last)).in_key(key))
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        let (key, last) = (self.key, self.field.last().entry);
+        de::Deserializer::deserialize_tuple(self.field, len, visitor).map_err(|e| e.at(|| entry_location(last)).in_key(key))
+    }
+
+    fn struct_variant<V>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        let (key, last, opts) = (self.key, self.field.last().entry, self.field.opts);
+        warnings_in_key(opts, key, || de::Deserializer::deserialize_struct(self.field, "", fields, visitor))
+            .map_err(|e| e.at(|| entry_location(last)).in_key(key))
+    }
+}
+
+/// Every occurrence of the same key within a category
+///
+/// Sequences are read from all occurrences, categories of the same name are merged
+/// and for everything else the last assignment wins
+struct FieldDeserializer<'a> {
+    /// The key as written in the text
+    key: &'a str,
+    items: Vec<&'a Entry>,
+    opts: &'a Options,
+    key_field: Option<&'a str>,
+    injected: Option<(&'a str, &'a str)>,
+}
+
+impl<'a> FieldDeserializer<'a> {
+    fn new(key: &'a str, items: Vec<&'a Entry>, opts: &'a Options) -> Self {
+        FieldDeserializer { key, items, opts, key_field: None, injected: None }
+    }
+
+    fn last(&self) -> EntryDeserializer<'a> {
+        // Fields are only created from at least one entry
+        EntryDeserializer { entry: self.items[self.items.len() - 1], keyword: false, opts: self.opts }
+    }
+
+    /// The last assignment `read` accepts, skipping those within `noerror` regions it does not as warnings
+    ///
+    /// serde can not take back a value once it asked for it, so `read` tries them with [`IgnoredAny`](de::IgnoredAny) first
+    fn readable(&self, read: impl Fn(EntryDeserializer<'a>) -> Result<de::IgnoredAny>) -> EntryDeserializer<'a> {
+        let entry = |item| EntryDeserializer { entry: item, keyword: false, opts: self.opts };
+
+        for (index, item) in self.items.iter().copied().enumerate().rev() {
+            if !item.noerror || index == 0 {
+                return entry(item);
+            }
+            match read(entry(item)) {
+                Ok(_) => return entry(item),
+                Err(e) => self.opts.warnings.borrow_mut().push(e.at(|| entry_location(item)))
+            }
+        }
+
+        // Fields are only created from at least one entry
+        self.last()
+    }
+
+    fn categories(&self) -> Option<CategoryDeserializer<'a>> {
+        let mut entries = vec![];
+        for item in self.items.iter() {
+            match &item.node {
+                Node::Category(category) => entries.extend(category.entries.iter()),
+                Node::Value(_) => return None,
+            }
+        }
+
+        Some(CategoryDeserializer { entries, opts: self.opts, injected: self.injected })
+    }
+
+    /// The entries of a map read from these categories
+    ///
+    /// Keyed special categories (`device[key] { }` or `device { name = key }`) are one entry each,
+    /// for all other categories their keys become the entries
+    fn map_entries(&self) -> Option<Vec<(&'a str, FieldDeserializer<'a>)>> {
+        let key_field = self.key_field.or(self.opts.special_category_key.as_deref());
+        let mut entries: Vec<(&'a str, FieldDeserializer<'a>)> = vec![];
+        let mut add = |key: &'a str, item: &'a Entry, injected: Option<(&'a str, &'a str)>| {
+            match entries.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, field)) => {
+                    field.items.push(item);
+                    field.injected = field.injected.or(injected);
+                },
+                None => entries.push((key, FieldDeserializer { injected, ..FieldDeserializer::new(key, vec![item], self.opts) })),
+            }
+        };
+
+        for item in self.items.iter().copied() {
+            let Node::Category(category) = &item.node else {
+                return None;
+            };
+
+            if let Some(key) = item.special_key.as_deref() {
+                add(key, item, key_field.map(|field| (field, key)));
+            } else if let Some(key) = key_field.and_then(|field| category.value_of(field)) {
+                add(key, item, None);
+            } else {
+                for entry in category.entries.iter() {
+                    add(&entry.key, entry, None);
+                }
+            }
+        }
+
+        Some(entries)
+    }
+}
+
+macro_rules! forward_to_last {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+                self.readable(|entry| entry.$method(de::IgnoredAny)).$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for FieldDeserializer<'_> {
+    type Error = Error;
+
+    forward_to_last! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_f32 deserialize_f64
+        deserialize_char deserialize_str deserialize_string deserialize_bytes deserialize_byte_buf
+        deserialize_identifier deserialize_unit
+    }
+
+    /// Categories of the same name are the same category, like `decoration:blur:size` and `blur { }` within `decoration { }`,
+    /// other repeated keys are a sequence
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        match self.categories() {
+            Some(category) if self.items.iter().all(|item| item.special_key.is_none()) => category.deserialize_map(visitor),
+            _ if self.items.len() > 1 => self.deserialize_seq(visitor),
+            _ => self.last().deserialize_any(visitor)
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        match &self.last().entry.node {
+            Node::Value(value) if value.text.is_empty() => visitor.visit_none(),
+            _ => visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        self.last().deserialize_unit_struct(name, visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        if matches!(name, VARREF_TOKEN | INFERRED_TOKEN) {
+            self.last().deserialize_newtype_struct(name, visitor)
+        } else {
+            visitor.visit_newtype_struct(self)
+        }
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        // An empty sequence is written as a lone `key = `
+        if let [Entry { node: Node::Value(value), .. }] = self.items[..] {
+            if value.text.is_empty() {
+                return visitor.visit_seq(ItemsAccess { items: vec![].into_iter(), keywords: false, opts: self.opts });
+            }
+        }
+        visitor.visit_seq(ItemsAccess { items: self.items.into_iter(), keywords: false, opts: self.opts })
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        self.last().deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(self, name: &'static str, len: usize, visitor: V) -> Result<V::Value>
+        where V: Visitor<'de> {
+        self.last().deserialize_tuple_struct(name, len, visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        match self.map_entries() {
+            Some(entries) => {
+                let fields = entries.into_iter().map(|(key, field)| (key, FieldValue::Field(field)));
+                visitor.visit_map(CategoryAccess { fields: fields.collect::<Vec<_>>().into_iter(), value: None, opts: self.opts })
+            },
+            None => self.last().deserialize_map(visitor)
+        }
+    }
+
+    fn deserialize_struct<V>(self, name: &'static str, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+        where V: Visitor<'de> {
+        match self.categories() {
+            Some(category) => category.deserialize_struct(name, fields, visitor),
+            None => self.last().deserialize_struct(name, fields, visitor)
+        }
+    }
+
+    fn deserialize_enum<V>(self, name: &'static str, variants: &'static [&'static str], visitor: V) -> Result<V::Value>
+        where V: Visitor<'de> {
+        match self.categories() {
+            Some(category) => category.deserialize_enum(name, variants, visitor),
+            None => self.last().deserialize_enum(name, variants, visitor)
+        }
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_unit()
+    }
+}
+
+/// Each entry becomes one element of the sequence
+struct ItemsAccess<'a> {
+    items: std::vec::IntoIter<&'a Entry>,
+    /// The key of each entry is the variant of an enum, see [`Deserializer::keyword_variants`]
+    keywords: bool,
+    opts: &'a Options,
+}
+
+impl<'de> de::SeqAccess<'de> for ItemsAccess<'_> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>> where T: DeserializeSeed<'de> {
+        match self.items.next() {
+            Some(entry) => seed.deserialize(EntryDeserializer { entry, keyword: self.keywords, opts: self.opts })
+                .map(Some)
+                .map_err(|e| e.at(|| entry_location(entry))),
+            None => Ok(None)
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len())
+    }
+}
+
+/// A single `key = value` line or `key { }` category
+struct EntryDeserializer<'a> {
+    entry: &'a Entry,
+    /// Enums take their variant from the key
+    keyword: bool,
+    opts: &'a Options,
+}
+
+macro_rules! forward_to_node {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+                match &self.entry.node {
+                    Node::Value(value) => ValueDeserializer::new(value, self.opts).$method(visitor),
+                    Node::Category(category) => CategoryDeserializer::new(&category.entries, self.opts).$method(visitor),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for EntryDeserializer<'_> {
+    type Error = Error;
+
+    forward_to_node! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_f32 deserialize_f64
+        deserialize_char deserialize_str deserialize_string deserialize_bytes deserialize_byte_buf
+        deserialize_identifier deserialize_unit deserialize_any deserialize_option deserialize_seq
+        deserialize_map deserialize_ignored_any
+    }
+
+    fn deserialize_unit_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        match &self.entry.node {
+            Node::Value(value) => ValueDeserializer::new(value, self.opts).deserialize_unit_struct(name, visitor),
+            Node::Category(category) => CategoryDeserializer::new(&category.entries, self.opts).deserialize_unit_struct(name, visitor),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        match &self.entry.node {
+            Node::Value(value) if matches!(name, VARREF_TOKEN | INFERRED_TOKEN) => ValueDeserializer::new(value, self.opts).deserialize_newtype_struct(name, visitor),
+            _ => visitor.visit_newtype_struct(self)
+        }
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        match &self.entry.node {
+            Node::Value(value) => ValueDeserializer::new(value, self.opts).deserialize_tuple(len, visitor),
+            Node::Category(category) => CategoryDeserializer::new(&category.entries, self.opts).deserialize_tuple(len, visitor),
+        }
+    }
+
+    fn deserialize_tuple_struct<V>(self, name: &'static str, len: usize, visitor: V) -> Result<V::Value>
+        where V: Visitor<'de> {
+        match &self.entry.node {
+            Node::Value(value) => ValueDeserializer::new(value, self.opts).deserialize_tuple_struct(name, len, visitor),
+            Node::Category(category) => CategoryDeserializer::new(&category.entries, self.opts).deserialize_tuple_struct(name, len, visitor),
+        }
+    }
+
+    fn deserialize_struct<V>(self, name: &'static str, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+        where V: Visitor<'de> {
+        match &self.entry.node {
+            Node::Value(value) => ValueDeserializer::new(value, self.opts).deserialize_struct(name, fields, visitor),
+            Node::Category(category) => CategoryDeserializer::new(&category.entries, self.opts).deserialize_struct(name, fields, visitor),
+        }
+    }
+
+    fn deserialize_enum<V>(self, name: &'static str, variants: &'static [&'static str], visitor: V) -> Result<V::Value>
+        where V: Visitor<'de> {
+        if self.keyword {
+            let key = self.entry.key.as_str();
+            return visitor.visit_enum(CategoryEnum { key, field: FieldDeserializer::new(key, vec![self.entry], self.opts) });
+        }
+
+        match &self.entry.node {
+            Node::Value(value) => ValueDeserializer::new(value, self.opts).deserialize_enum(name, variants, visitor),
+            Node::Category(category) => CategoryDeserializer::new(&category.entries, self.opts).deserialize_enum(name, variants, visitor),
+        }
+    }
+}
+
+/// The text right of the `=`, or one element of a tupple
+struct ValueDeserializer<'a> {
+    text: &'a str,
+    /// The text before variables were expanded
+    raw: Option<&'a str>,
+    /// Where the raw text starts
+    position: Option<&'a Position>,
+    opts: &'a Options,
+}
+
+impl<'a> ValueDeserializer<'a> {
+    fn new(value: &'a Value, opts: &'a Options) -> Self {
+        ValueDeserializer { text: &value.text, raw: Some(&value.raw), position: Some(&value.position), opts }
+    }
+
+    fn text(text: &'a str, opts: &'a Options) -> Self {
+        ValueDeserializer { text, raw: None, position: None, opts }
+    }
+
+    /// The name of the variable, if the value is nothing but a reference to one
+    fn var_ref(&self) -> Option<&'a str> {
+        let name = self.raw?.strip_prefix('$')?;
+        (!name.is_empty() && name.chars().all(is_name_char)).then_some(name)
+    }
+
+    /// Reads a bool or number with one of the [`scalar`](crate::scalar) parsers
+    fn scalar<T>(&self, expected: &str, parse: impl FnOnce(&str) -> std::result::Result<T, String>) -> Result<T> {
+        parse(self.text).map_err(|e| Error::Message(format!("expected {expected}, found `{}`: {e}", self.text)))
+    }
+
+    /// See [`escape`](crate::escape) for when the serializer wraps strings in `"`
+    fn unquote(&self) -> Cow<'a, str> {
+        unquote(self.text)
+    }
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer<'_> {
+    type Error = Error;
+
+    /// The type is inferred from how the value is written, see [`Value`](crate::Value),
+    /// this is what `#[serde(flatten)]`, internally tagged and untagged enums see
+    ///
+    /// Vec2s, colors and gradients have no equivalent, they stay text for [`Vec2`](crate::Vec2), [`Color`](crate::Color)
+    /// and [`Gradient`](crate::Gradient) to read, which keeps the text for [`Inferred`](crate::Inferred) too
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        match infer(&self.unquote()) {
+            types::Value::Bool(bool) => visitor.visit_bool(bool),
+            types::Value::Int(int) => visitor.visit_i64(int),
+            types::Value::Float(float) => visitor.visit_f64(float),
+            _ => self.deserialize_str(visitor)
+        }
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_bool(self.scalar("a bool", parse_bool)?)
+    }
+
+    fn deserialize_i8<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_i8(self.scalar("an integer", |text| parse_int(text, i8::MIN..=i8::MAX))?)
+    }
+
+    fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_i16(self.scalar("an integer", |text| parse_int(text, i16::MIN..=i16::MAX))?)
+    }
+
+    fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_i32(self.scalar("an integer", |text| parse_int(text, i32::MIN..=i32::MAX))?)
+    }
+
+    fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_i64(self.scalar("an integer", |text| parse_int(text, i64::MIN..=i64::MAX))?)
+    }
+
+    fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_u8(self.scalar("an unsigned integer", |text| parse_int(text, u8::MIN..=u8::MAX))?)
+    }
+
+    fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_u16(self.scalar("an unsigned integer", |text| parse_int(text, u16::MIN..=u16::MAX))?)
+    }
+
+    fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_u32(self.scalar("an unsigned integer", |text| parse_int(text, u32::MIN..=u32::MAX))?)
+    }
+
+    fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_u64(self.scalar("an unsigned integer", |text| parse_int(text, u64::MIN..=u64::MAX))?)
+    }
+
+    fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_f32(self.scalar("a float", |text| parse_float(text, f32::MAX as f64))? as f32)
+    }
+
+    fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_f64(self.scalar("a float", |text| parse_float(text, f64::MAX))?)
+    }
+
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        let text = self.unquote();
+        let mut chars = text.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => visitor.visit_char(c),
+            _ => Err(Error::Message(format!("expected a single character, found `{}`", self.text)))
+        }
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_str(&self.unquote())
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V>(self, _visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        Err(Error::NotSupported("Bytes"))
+    }
+
+    fn deserialize_byte_buf<V>(self, _visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        Err(Error::NotSupported("Bytes"))
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        if self.text.is_empty() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        if name == VARREF_TOKEN {
+            let reference = if self.opts.resolve_var_refs { None } else { self.var_ref() };
+            visitor.visit_seq(VarRefAccess { reference: Some(reference), value: Some(self) })
+        } else if name == INFERRED_TOKEN {
+            self.deserialize_str(visitor)
+        } else {
+            visitor.visit_newtype_struct(self)
+        }
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        let mut elements = Elements::new(&self);
+        let len = elements.remaining();
+        visitor.visit_seq(TuppleAccess { elements: &mut elements, remaining: len, takes_rest: true })
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        let mut elements = Elements::new(&self);
+        let value = visitor.visit_seq(TuppleAccess { elements: &mut elements, remaining: len, takes_rest: true })?;
+        elements.end()?;
+        Ok(value)
+    }
+
+    fn deserialize_tuple_struct<V>(self, _name: &'static str, len: usize, visitor: V) -> Result<V::Value>
+        where V: Visitor<'de> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V>(self, _visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        Err(Error::NotSupported("Maps within a value"))
+    }
+
+    /// Written as `key: value` elements, like the matchers of `windowrulev2 = float, class:^(kitty)$`
+    fn deserialize_struct<V>(self, _name: &'static str, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+        where V: Visitor<'de> {
+        let mut elements = Elements::new(&self);
+        let value = visitor.visit_map(InlineStructAccess { elements: &mut elements, fields, value: None })?;
+        elements.end()?;
+        Ok(value)
+    }
+
+    /// The variant is the first element, followed by its data: `Tuple, 1, 2`
+    fn deserialize_enum<V>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value>
+        where V: Visitor<'de> {
+        let mut elements = Elements::new(&self);
+        let value = visitor.visit_enum(ElementsEnum { elements: &mut elements, takes_rest: true })?;
+        elements.end()?;
+        Ok(value)
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_unit()
+    }
+}
+
+/// The comma seperated elements of a tupple
+struct Elements<'a> {
+    text: &'a str,
+    ranges: Vec<Range<usize>>,
+    /// The same elements before variables were expanded, if they still line up
+    raw: Option<(&'a str, Vec<Range<usize>>)>,
+    position: Option<&'a Position>,
+    next: usize,
+    opts: &'a Options,
+}
+
+impl<'a> Elements<'a> {
+    fn new(value: &ValueDeserializer<'a>) -> Self {
+        let ranges = split_elements(value.text);
+        let raw = value.raw
+            .map(|raw| (raw, split_elements(raw)))
+            .filter(|(_, raw_ranges)| raw_ranges.len() == ranges.len());
+
+        Elements { text: value.text, ranges, raw, position: value.position, next: 0, opts: value.opts }
+    }
+
+    /// Attaches the location of the elements from `first` up to the next one to the error
+    fn locate(&self, error: Error, first: usize) -> Error {
+        let (Some((raw, ranges)), Some(position)) = (&self.raw, self.position) else {
+            return error;
+        };
+        let (Some(start), Some(end)) = (ranges.get(first), ranges.get(self.next.max(first + 1) - 1)) else {
+            return error;
+        };
+
+        error.at(|| position.advance(raw, start.start).location(end.end - start.start, ""))
+    }
+
+    fn remaining(&self) -> usize {
+        self.ranges.len() - self.next
+    }
+
+    fn next(&mut self) -> Option<ValueDeserializer<'a>> {
+        let range = self.ranges.get(self.next)?.clone();
+        let raw = self.raw.as_ref().map(|(raw, ranges)| &raw[ranges[self.next].clone()]);
+        self.next += 1;
+        Some(ValueDeserializer { text: &self.text[range], raw, position: None, opts: self.opts })
+    }
+
+    /// The next element split into its key and value, if it is `key: value` with one of the fields as key
+    ///
+    /// Only the first `:` separates them, so values such as `title:^(foo: bar)$` keep theirs
+    fn next_field(&mut self, fields: &[&str]) -> Option<(&'a str, ValueDeserializer<'a>)> {
+        let text: &'a str = self.text;
+        let (key, value) = text[self.ranges.get(self.next)?.clone()].split_once(':')?;
+        let key = key.trim();
+        if !fields.contains(&key) {
+            return None;
+        }
+
+        let raw = self.raw.as_ref()
+            .and_then(|(raw, ranges)| raw[ranges[self.next].clone()].split_once(':'))
+            .map(|(_, value)| value.trim());
+        self.next += 1;
+        Some((key, ValueDeserializer { text: value.trim(), raw, position: None, opts: self.opts }))
+    }
+
+    /// Everything that is left, commas included
+    fn rest(&mut self) -> Option<ValueDeserializer<'a>> {
+        let start = self.ranges.get(self.next)?.start;
+        let end = self.ranges[self.ranges.len() - 1].end;
+        let raw = self.raw.as_ref().map(|(raw, ranges)| &raw[ranges[self.next].start..ranges[ranges.len() - 1].end]);
+        self.next = self.ranges.len();
+        Some(ValueDeserializer { text: &self.text[start..end], raw, position: None, opts: self.opts })
+    }
+
+    fn end(&self) -> Result<()> {
+        if self.remaining() == 0 {
+            Ok(())
+        } else {
+            Err(Error::Message(format!("too many elements in `{}`", self.text)))
+        }
+    }
+}
+
+fn split_elements(text: &str) -> Vec<Range<usize>> {
+    let mut ranges = vec![];
+    let mut start = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+
+    for (index, c) in text.char_indices() {
+        if std::mem::take(&mut escaped) {
+            continue;
+        }
+
+        match c {
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                ranges.push(trimmed(text, start..index));
+                start = index + 1;
+            },
+            _ => ()
+        }
+    }
+    ranges.push(trimmed(text, start..text.len()));
+
+    ranges
+}
+
+fn trimmed(text: &str, range: Range<usize>) -> Range<usize> {
+    let slice = &text[range.clone()];
+    let start = range.start + (slice.len() - slice.trim_start().len());
+    let end = start + slice.trim().len();
+    start..end
+}
+
+struct TuppleAccess<'b, 'a> {
+    elements: &'b mut Elements<'a>,
+    remaining: usize,
+    /// Only the last element of the outermost tupple may swallow additional commas, if it is read as text
+    takes_rest: bool,
+}
+
+impl<'de> de::SeqAccess<'de> for TuppleAccess<'_, '_> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>> where T: DeserializeSeed<'de> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+
+        let takes_rest = self.takes_rest && self.remaining == 0;
+        seed.deserialize(ElementDeserializer { elements: &mut *self.elements, takes_rest }).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
+
+/// A single element of a tupple
+/// Nested tupples take as many elements as they need, as the serializer writes them flattened
+struct ElementDeserializer<'b, 'a> {
+    elements: &'b mut Elements<'a>,
+    takes_rest: bool,
+}
+
+impl<'a> ElementDeserializer<'_, 'a> {
+    /// `text` elements take the rest, if this one may, every other type is a single element
+    fn next(&mut self, text: bool) -> Result<ValueDeserializer<'a>> {
+        let value = if self.takes_rest && text { self.elements.rest() } else { self.elements.next() };
+
+        match value {
+            Some(value) => Ok(value),
+            None => Err(Error::Message(format!("not enough elements in `{}`", self.elements.text)))
+        }
+    }
+}
+
+macro_rules! forward_to_element {
+    ($text:literal => $($method:ident)*) => {
+        $(
+            fn $method<V>(mut self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+                let first = self.elements.next;
+                let res = self.next($text)?.$method(visitor);
+                res.map_err(|e| self.elements.locate(e, first))
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for ElementDeserializer<'_, '_> {
+    type Error = Error;
+
+    forward_to_element! {
+        true => deserialize_str deserialize_string deserialize_bytes deserialize_byte_buf
+    }
+
+    forward_to_element! {
+        false => deserialize_any deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_f32 deserialize_f64
+        deserialize_char deserialize_identifier deserialize_unit deserialize_map deserialize_ignored_any
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        match self.elements.ranges.get(self.elements.next) {
+            None => visitor.visit_none(),
+            Some(range) if range.is_empty() => {
+                self.elements.next += 1;
+                visitor.visit_none()
+            },
+            Some(_) => visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit_struct<V>(mut self, name: &'static str, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        let first = self.elements.next;
+        let res = self.next(false)?.deserialize_unit_struct(name, visitor);
+        res.map_err(|e| self.elements.locate(e, first))
+    }
+
+    fn deserialize_newtype_struct<V>(mut self, name: &'static str, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        if matches!(name, VARREF_TOKEN | INFERRED_TOKEN) {
+            let first = self.elements.next;
+            let res = self.next(true)?.deserialize_newtype_struct(name, visitor);
+            res.map_err(|e| self.elements.locate(e, first))
+        } else {
+            visitor.visit_newtype_struct(self)
+        }
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        let len = self.elements.remaining();
+        visitor.visit_seq(TuppleAccess { elements: self.elements, remaining: len, takes_rest: false })
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_seq(TuppleAccess { elements: self.elements, remaining: len, takes_rest: self.takes_rest })
+    }
+
+    fn deserialize_tuple_struct<V>(self, _name: &'static str, len: usize, visitor: V) -> Result<V::Value>
+        where V: Visitor<'de> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    /// Takes the following `key: value` elements, as long as the struct knows their key
+    fn deserialize_struct<V>(self, _name: &'static str, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+        where V: Visitor<'de> {
+        let first = self.elements.next;
+        let value = visitor.visit_map(InlineStructAccess { elements: &mut *self.elements, fields, value: None });
+        value.map_err(|e| self.elements.locate(e, first))
+    }
+
+    /// Like nested tupples, the variant takes as many of the following elements as its data needs
+    fn deserialize_enum<V>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value>
+        where V: Visitor<'de> {
+        visitor.visit_enum(ElementsEnum { elements: self.elements, takes_rest: self.takes_rest })
+    }
+}
+
+/// An enum within a value, the variant is an element followed by the elements of its data
+struct ElementsEnum<'b, 'a> {
+    elements: &'b mut Elements<'a>,
+    takes_rest: bool,
+}
+
+impl<'de> de::EnumAccess<'de> for ElementsEnum<'_, '_> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self)> where V: DeserializeSeed<'de> {
+        let first = self.elements.next;
+        let Some(variant) = self.elements.next() else {
+            return Err(Error::Message(format!("expected a variant, found the end of `{}`", self.elements.text)));
+        };
+
+        let value = seed.deserialize(variant).map_err(|e| self.elements.locate(e, first))?;
+        Ok((value, self))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for ElementsEnum<'_, '_> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value> where T: DeserializeSeed<'de> {
+        seed.deserialize(ElementDeserializer { elements: self.elements, takes_rest: self.takes_rest })
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        visitor.visit_seq(TuppleAccess { elements: self.elements, remaining: len, takes_rest: self.takes_rest })
+    }
+
+    fn struct_variant<V>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value> where V: Visitor<'de> {
+        de::Deserializer::deserialize_struct(ElementDeserializer { elements: self.elements, takes_rest: self.takes_rest }, "", fields, visitor)
+    }
+}
+
+/// A struct within a value, its fields are the `key: value` elements
+struct InlineStructAccess<'b, 'a> {
+    elements: &'b mut Elements<'a>,
+    fields: &'static [&'static str],
+    value: Option<(usize, ValueDeserializer<'a>)>,
+}
+
+impl<'de> de::MapAccess<'de> for InlineStructAccess<'_, '_> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>> where K: DeserializeSeed<'de> {
+        let index = self.elements.next;
+        match self.elements.next_field(self.fields) {
+            Some((key, value)) => {
+                self.value = Some((index, value));
+                seed.deserialize(ValueDeserializer::text(key, self.elements.opts)).map(Some)
+            },
+            None => Ok(None)
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value> where V: DeserializeSeed<'de> {
+        match self.value.take() {
+            Some((index, value)) => seed.deserialize(value).map_err(|e| self.elements.locate(e, index)),
+            None => Err(Error::UnexpectedSequence("value requested before its key".to_string()))
+        }
+    }
+}
+
+/// Hands a [`VarRef`](crate::VarRef) the name of the referenced variable (empty if it is none), followed by the value
+struct VarRefAccess<'a> {
+    reference: Option<Option<&'a str>>,
+    value: Option<ValueDeserializer<'a>>,
+}
+
+impl<'de> de::SeqAccess<'de> for VarRefAccess<'_> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>> where T: DeserializeSeed<'de> {
+        if let Some(reference) = self.reference.take() {
+            return seed.deserialize(reference.unwrap_or_default().into_deserializer()).map(Some);
+        }
+
+        match self.value.take() {
+            Some(value) => seed.deserialize(value).map(Some),
+            None => Ok(None)
+        }
+    }
+}
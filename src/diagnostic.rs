@@ -0,0 +1,157 @@
+//! Renders errors together with the line they happened on, in the style of rustc
+
+use std::fmt::{self, Display, Formatter, Write};
+
+use crate::{Error, Location, SyntaxKind};
+
+const RED: &str = "\x1b[1;31m";
+const BLUE: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// An [`Error`] rendered with the failing line, the span underlined, the key path and a hint
+///
+/// ```text
+/// error: expected a float, found `big`: invalid float literal
+///  --> hyprland.conf:5:16
+///   |
+/// 5 |         size = big
+///   |                ^^^ in `decoration:blur:size`
+/// ```
+///
+/// The source text has to be handed in, errors in other files (such as `source =` includes)
+/// are matched with the text given for their file name, and rendered without the line if there is none
+pub struct Diagnostic<'a> {
+    error: &'a Error,
+    sources: Vec<(Option<&'a str>, &'a str)>,
+    colored: bool,
+}
+
+impl<'a> Diagnostic<'a> {
+    pub fn new(error: &'a Error) -> Self {
+        Diagnostic { error, sources: vec![], colored: false }
+    }
+
+    /// The text that was deserialized, used for every location without a file name
+    pub fn source(mut self, text: &'a str) -> Self {
+        self.sources.push((None, text));
+        self
+    }
+
+    /// The text of the file called `name`
+    pub fn file(mut self, name: &'a str, text: &'a str) -> Self {
+        self.sources.push((Some(name), text));
+        self
+    }
+
+    /// Colors the output with ANSI escape codes
+    pub fn colored(mut self, colored: bool) -> Self {
+        self.colored = colored;
+        self
+    }
+
+    fn paint(&self, color: &'static str) -> &'static str {
+        if self.colored { color } else { "" }
+    }
+
+    /// The text of the file the location is in, `None` for a file that was not handed in
+    fn text(&self, location: &Location) -> Option<&'a str> {
+        let file = location.file.as_deref();
+        self.sources.iter().find(|(name, _)| *name == file).map(|(_, text)| *text)
+    }
+
+    fn snippet(&self, f: &mut Formatter<'_>, location: &Location, gutter: &str) -> fmt::Result {
+        let Some(line) = self.text(location).and_then(|text| text.split('\n').nth(location.line.saturating_sub(1))) else {
+            return Ok(());
+        };
+        let line = line.trim_end_matches('\r');
+        let (blue, red, reset) = (self.paint(BLUE), self.paint(RED), self.paint(RESET));
+
+        // Tabs are kept, so the underline lines up however wide they are displayed
+        let before = line.chars().take(location.column.saturating_sub(1));
+        let padding = before.map(|c| if c == '\t' { '\t' } else { ' ' }).collect::<String>();
+
+        let start = line.char_indices().nth(location.column.saturating_sub(1)).map(|(i, _)| i).unwrap_or(line.len());
+        let end = (start + location.span.len()).min(line.len());
+        let underline = "^".repeat(line.get(start..end).map(|s| s.chars().count()).unwrap_or_default().max(1));
+
+        writeln!(f, "{blue}{gutter} |{reset}")?;
+        writeln!(f, "{blue}{:>width$} |{reset} {line}", location.line, width = gutter.len())?;
+        write!(f, "{blue}{gutter} |{reset} {padding}{red}{underline}{reset}")?;
+        if !location.path.is_empty() {
+            write!(f, " {red}in `{}`{reset}", location.path)?;
+        }
+        f.write_char('\n')
+    }
+}
+
+impl Display for Diagnostic<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let (red, blue, bold, reset) = (self.paint(RED), self.paint(BLUE), self.paint(BOLD), self.paint(RESET));
+
+        writeln!(f, "{red}error{reset}{bold}: {}{reset}", self.error.inner())?;
+
+        let gutter = match self.error.location() {
+            Some(location) => {
+                let gutter = " ".repeat(location.line.to_string().len());
+                writeln!(f, "{gutter}{blue}-->{reset} {location}")?;
+                self.snippet(f, location, &gutter)?;
+                gutter
+            },
+            None => String::new()
+        };
+
+        if let Some(hint) = hint(self.error) {
+            writeln!(f, "{gutter} {blue}={reset} {bold}hint{reset}: {hint}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// What is most likely to fix the error
+pub(crate) fn hint(error: &Error) -> Option<&'static str> {
+    match error.inner() {
+        Error::Syntax(SyntaxKind::UndefinedVariable, _) => Some("variables have to be defined with `$name = value` before they are used"),
+        Error::Syntax(SyntaxKind::RecursiveVariable, _) => Some("a variable can not be part of its own value"),
+        Error::Syntax(SyntaxKind::UnclosedExpression, _) => Some("expressions are closed with `}}` on the same line"),
+        Error::Syntax(SyntaxKind::UnclosedParenthesis, _) => Some("every `(` within an expression needs a `)`"),
+        Error::Syntax(SyntaxKind::UnclosedCategory, _) => Some("every `category {` needs a `}` on a line of its own"),
+        Error::Syntax(SyntaxKind::UnmatchedBracket, _) => Some("there is one `}` too many, or a `category {` is missing"),
+        Error::MissingField(_) => Some("add the key to this category"),
+        Error::InvalidKey(_) => Some("keys can not be empty, start with `$` or contain whitespace or any of `= { } # , : [ ] \"`"),
+        _ => None
+    }
+}
+
+/// The location becomes a label, the source text has to be attached to the report,
+/// for example with `miette::Report::new(error).with_source_code(text)`
+///
+/// Errors in files read through `source =` have no label, their spans are not within that text
+#[cfg(feature = "miette")]
+impl miette::Diagnostic for Error {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        let code = match self.inner() {
+            Error::Message(_) => "serde_hyprlang::message",
+            Error::NotSupported(_) => "serde_hyprlang::not_supported",
+            Error::UnexpectedSequence(_) => "serde_hyprlang::unexpected_sequence",
+            Error::InvalidKey(_) => "serde_hyprlang::invalid_key",
+            Error::MissingField(_) => "serde_hyprlang::missing_field",
+            Error::Syntax(..) => "serde_hyprlang::syntax",
+            Error::Include(_) => "serde_hyprlang::include",
+            Error::Located { .. } => return None,
+        };
+        Some(Box::new(code))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        hint(self).map(|hint| Box::new(hint) as Box<dyn Display>)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+        let location = self.location().filter(|location| !location.included)?;
+        let label = (!location.path.is_empty()).then(|| format!("in `{}`", location.path));
+        let span = miette::LabeledSpan::new(label, location.span.start, location.span.len());
+        Some(Box::new(std::iter::once(span)))
+    }
+}
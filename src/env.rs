@@ -0,0 +1,33 @@
+//! Where `$HOME` and other variables that are not defined in the text come from
+
+use std::collections::{BTreeMap, HashMap};
+use std::hash::BuildHasher;
+
+/// Looks up environment variables, for `$name`s the text does not define itself
+pub trait Environment {
+    /// The value of the variable, `None` if it is not set
+    fn var(&self, name: &str) -> Option<String>;
+}
+
+/// The environment of the running process, through [`std::env::var`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemEnvironment;
+
+impl Environment for SystemEnvironment {
+    fn var(&self, name: &str) -> Option<String> {
+        std::env::var(name).ok()
+    }
+}
+
+/// A fixed set of variables, for tests or sandboxed tools
+impl<S: BuildHasher> Environment for HashMap<String, String, S> {
+    fn var(&self, name: &str) -> Option<String> {
+        self.get(name).cloned()
+    }
+}
+
+impl Environment for BTreeMap<String, String> {
+    fn var(&self, name: &str) -> Option<String> {
+        self.get(name).cloned()
+    }
+}
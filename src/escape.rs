@@ -0,0 +1,124 @@
+//! How strings are written into values and keys, and read back out of them
+//!
+//! Hyprlang itself has no quoting, so values are written as they are whenever that reads back the same.
+//! Only strings which would otherwise be cut apart or trimmed are wrapped in `"`:
+//! those containing a `,`, a line break or a tab, starting with a `"`, ending with a `\` (which would continue the line)
+//! or with whitespace at either end.
+//! Within the quotes `\\`, `\"`, `\n`, `\r` and `\t` are escaped, any other `\` is kept as is.
+//!
+//! Independent of the quotes, every `#` is written as `##`, as a single one starts a comment.
+//! Every `$` and `{{` is written with a `\` in front, so they are not expanded as a variable or an expression.
+//!
+//! Keys can not be quoted, so keys which hyprlang would read differently are an [`Error::InvalidKey`]
+
+use std::borrow::Cow;
+
+use crate::{Error, Result};
+
+/// The string as it is written after the `=`
+pub(crate) fn quote(value: &str) -> String {
+    let needs_quotes = value.contains([',', '\n', '\r', '\t'])
+        || value.starts_with('"')
+        || value.ends_with('\\')
+        || value.trim() != value;
+
+    let value = value.replace('#', "##");
+    if !needs_quotes {
+        return escape_expansion(&value);
+    }
+
+    let mut output = String::with_capacity(value.len() + 2);
+    output.push('"');
+    for c in value.chars() {
+        match c {
+            '\\' => output += "\\\\",
+            '"' => output += "\\\"",
+            '\n' => output += "\\n",
+            '\r' => output += "\\r",
+            '\t' => output += "\\t",
+            c => output.push(c)
+        }
+    }
+    output.push('"');
+
+    escape_expansion(&output)
+}
+
+/// Keeps every `$` and `{{` from being expanded, which happens before the quotes are removed
+fn escape_expansion(text: &str) -> String {
+    text.replace('$', "\\$").replace("{{", "\\{{")
+}
+
+/// Reverses [`quote`], text that is not entirely within quotes is returned as is
+/// (the `##` were already handled by [`unescape_hashes`])
+pub(crate) fn unquote(text: &str) -> Cow<'_, str> {
+    let Some(inner) = text.strip_prefix('"').and_then(|t| t.strip_suffix('"')) else {
+        return Cow::Borrowed(text);
+    };
+    if quoted_len(text) != Some(text.len()) {
+        // Something like `"a" "b"`, which we did not write
+        return Cow::Borrowed(text);
+    }
+
+    let mut output = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            output.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('\\') => output.push('\\'),
+            Some('"') => output.push('"'),
+            Some('n') => output.push('\n'),
+            Some('r') => output.push('\r'),
+            Some('t') => output.push('\t'),
+            Some(other) => {
+                output.push('\\');
+                output.push(other);
+            },
+            None => output.push('\\')
+        }
+    }
+
+    Cow::Owned(output)
+}
+
+/// Turns the `##` left in by stripping comments back into `#`
+pub(crate) fn unescape_hashes(text: &str) -> String {
+    text.replace("##", "#")
+}
+
+/// The length of the quoted string at the start of the text, including both quotes
+pub(crate) fn quoted_len(text: &str) -> Option<usize> {
+    let mut chars = text.char_indices();
+    if chars.next()?.1 != '"' {
+        return None;
+    }
+
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            },
+            '"' => return Some(index + 1),
+            _ => ()
+        }
+    }
+
+    None
+}
+
+/// Keys are written as they are, so they may only contain what the parser reads back as one key
+pub(crate) fn check_key(key: &str) -> Result<()> {
+    let invalid = key.is_empty()
+        || key.starts_with('$')
+        || key.chars().any(|c| c.is_whitespace() || matches!(c, '=' | '{' | '}' | '#' | ',' | ':' | '[' | ']' | '"'));
+
+    if invalid {
+        Err(Error::InvalidKey(key.to_string()))
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,258 @@
+//! Arithmetic within values, `gaps_out = {{ $gap * 2 }}`
+//!
+//! Expressions know `+`, `-`, `*`, `/`, parentheses and a leading `-`, the usual precedence applies.
+//! Operands are numbers or variables, written with or without the `$`.
+//! As long as both sides are integers the arithmetic stays integral (`7 / 2` is `3`),
+//! as soon as one of them is a float the result is one too
+
+use std::fmt::{self, Display, Formatter};
+use std::ops::Range;
+
+use crate::parse::Position;
+use crate::variables::is_name_char;
+use crate::{Error, Result, SyntaxKind};
+
+/// The result of an expression
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Number {
+    Int(i64),
+    Float(f64),
+}
+
+impl Display for Number {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Number::Int(int) => write!(f, "{int}"),
+            Number::Float(float) => write!(f, "{float}"),
+        }
+    }
+}
+
+/// Evaluates the expression between the `{{` and `}}`
+///
+/// `variable` returns the value of a variable, by its name without the `$`.
+/// Errors are located within the expression, which counts as the first line
+///
+/// ```
+/// use serde_hyprlang::{eval, Number};
+///
+/// let gap = |name: &str| (name == "gap").then(|| "5".to_string());
+/// assert_eq!(eval("$gap * 2 + 1", gap), Ok(Number::Int(11)));
+/// assert_eq!(eval("(gap + 0.5) / 2", gap), Ok(Number::Float(2.75)));
+/// ```
+pub fn eval(expression: &str, variable: impl Fn(&str) -> Option<String>) -> Result<Number> {
+    let position = Position { line: 1, column: 1, ..Position::default() };
+    let mut lookup = |name: &str, _: &Position, _: usize| Ok(variable(name));
+    evaluate(expression, &position, "", &mut lookup)
+}
+
+/// Looks up a variable, `position` and `len` are of its name within the expression
+pub(crate) type Lookup<'l> = dyn FnMut(&str, &Position, usize) -> Result<Option<String>> + 'l;
+
+/// `position` is where the expression starts, and `path` the key it is assigned to, both are used for errors
+pub(crate) fn evaluate(expression: &str, position: &Position, path: &str, lookup: &mut Lookup<'_>) -> Result<Number> {
+    let mut evaluator = Evaluator { text: expression, index: 0, position, path, lookup, depth: 0 };
+
+    let (number, _) = evaluator.sum()?;
+    evaluator.skip_whitespace();
+    match evaluator.peek() {
+        None => Ok(number),
+        Some(c) => Err(evaluator.error(SyntaxKind::Other, evaluator.index..evaluator.index + c.len_utf8(), format!("expected an operator, found `{c}`")))
+    }
+}
+
+/// The number a variable or literal is written as
+fn parse_number(text: &str) -> Option<Number> {
+    if let Ok(int) = text.parse() {
+        return Some(Number::Int(int));
+    }
+
+    // Rust would also accept `inf` and `NaN`
+    let is_float = text.starts_with(|c: char| c.is_ascii_digit() || matches!(c, '-' | '+' | '.'))
+        && text.chars().all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '-' | '+'));
+    text.parse().ok().filter(|_| is_float).map(Number::Float)
+}
+
+/// The length of the number at the start of the text, a sign belongs to it right after the `e` of an exponent
+fn number_len(text: &str) -> usize {
+    let mut previous = ' ';
+    let len = text.find(|c: char| {
+        let exponent_sign = matches!(c, '+' | '-') && matches!(previous, 'e' | 'E');
+        previous = c;
+        !c.is_ascii_alphanumeric() && c != '.' && !exponent_sign
+    });
+    len.unwrap_or(text.len())
+}
+
+fn apply(operator: char, left: Number, right: Number) -> std::result::Result<Number, &'static str> {
+    const OVERFLOW: &str = "the result does not fit into a 64 bit integer";
+
+    match (left, right) {
+        (Number::Int(left), Number::Int(right)) => {
+            let result = match operator {
+                '+' => left.checked_add(right),
+                '-' => left.checked_sub(right),
+                '*' => left.checked_mul(right),
+                _ if right == 0 => return Err("division by zero"),
+                _ => left.checked_div(right),
+            };
+            result.map(Number::Int).ok_or(OVERFLOW)
+        },
+        (left, right) => {
+            let (left, right) = (as_float(left), as_float(right));
+            Ok(Number::Float(match operator {
+                '+' => left + right,
+                '-' => left - right,
+                '*' => left * right,
+                _ if right == 0.0 => return Err("division by zero"),
+                _ => left / right,
+            }))
+        }
+    }
+}
+
+fn as_float(number: Number) -> f64 {
+    match number {
+        Number::Int(int) => int as f64,
+        Number::Float(float) => float,
+    }
+}
+
+/// How deep parentheses and `-` may be nested, the evaluator recurses for each of them
+const MAX_DEPTH: usize = 256;
+
+/// A number together with the span of the text it was evaluated from
+type Operand = (Number, Range<usize>);
+
+struct Evaluator<'a, 'l> {
+    text: &'a str,
+    /// In bytes, into the text
+    index: usize,
+    position: &'a Position,
+    path: &'a str,
+    lookup: &'a mut Lookup<'l>,
+    /// How many parentheses and `-` the current factor is within
+    depth: usize,
+}
+
+impl Evaluator<'_, '_> {
+    fn peek(&self) -> Option<char> {
+        self.text[self.index..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.text[self.index..];
+        self.index += rest.len() - rest.trim_start().len();
+    }
+
+    fn error(&self, kind: SyntaxKind, span: Range<usize>, msg: impl Into<String>) -> Error {
+        Error::Syntax(kind, msg.into()).at(|| self.position.advance(self.text, span.start).location(span.len(), self.path))
+    }
+
+    /// Terms joined by `+` and `-`, together with the span they cover
+    fn sum(&mut self) -> Result<Operand> {
+        self.binary(&['+', '-'], Self::product)
+    }
+
+    /// Factors joined by `*` and `/`
+    fn product(&mut self) -> Result<Operand> {
+        self.binary(&['*', '/'], Self::factor)
+    }
+
+    fn binary(&mut self, operators: &[char], operand: fn(&mut Self) -> Result<Operand>) -> Result<Operand> {
+        let (mut number, mut span) = operand(self)?;
+
+        loop {
+            self.skip_whitespace();
+            let Some(operator) = self.peek().filter(|c| operators.contains(c)) else {
+                return Ok((number, span));
+            };
+            self.index += 1;
+
+            let (right, right_span) = operand(self)?;
+            span = span.start..right_span.end;
+            number = apply(operator, number, right).map_err(|msg| self.error(SyntaxKind::Other, span.clone(), msg))?;
+        }
+    }
+
+    /// A number, a variable, a negated factor or a sum in parentheses
+    fn factor(&mut self) -> Result<Operand> {
+        self.skip_whitespace();
+        let start = self.index;
+
+        if matches!(self.peek(), Some('-' | '(')) {
+            if self.depth == MAX_DEPTH {
+                return Err(self.error(SyntaxKind::Other, start..start + 1, format!("expressions can not be nested more than {MAX_DEPTH} levels deep")));
+            }
+            self.depth += 1;
+            let res = self.nested();
+            self.depth -= 1;
+            return res;
+        }
+
+        self.operand()
+    }
+
+    /// A negated factor or a sum in parentheses
+    fn nested(&mut self) -> Result<Operand> {
+        let start = self.index;
+
+        match self.peek() {
+            Some('-') => {
+                self.index += 1;
+                let (number, span) = self.factor()?;
+                let negated = match number {
+                    Number::Int(int) => int.checked_neg().map(Number::Int),
+                    Number::Float(float) => Some(Number::Float(-float)),
+                };
+                let negated = negated.ok_or_else(|| self.error(SyntaxKind::Other, start..span.end, "the result does not fit into a 64 bit integer"))?;
+                Ok((negated, start..span.end))
+            },
+            Some('(') => {
+                self.index += 1;
+                let (number, _) = self.sum()?;
+                self.skip_whitespace();
+                if self.peek() != Some(')') {
+                    return Err(self.error(SyntaxKind::UnclosedParenthesis, start..start + 1, "`(` is never closed"));
+                }
+                self.index += 1;
+                Ok((number, start..self.index))
+            },
+            _ => self.operand()
+        }
+    }
+
+    /// A number or a variable
+    fn operand(&mut self) -> Result<Operand> {
+        let start = self.index;
+        let rest = &self.text[start..];
+
+        match self.peek() {
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let len = number_len(rest);
+                self.index += len;
+                let number = parse_number(&rest[..len])
+                    .ok_or_else(|| self.error(SyntaxKind::Other, start..self.index, format!("`{}` is not a number", &rest[..len])))?;
+                Ok((number, start..self.index))
+            },
+            Some(c) if c == '$' || is_name_char(c) => {
+                let name_start = usize::from(c == '$');
+                let len = name_start + rest[name_start..].find(|c: char| !is_name_char(c)).unwrap_or(rest.len() - name_start);
+                self.index += len;
+                let name = &rest[name_start..len];
+                if name.is_empty() {
+                    return Err(self.error(SyntaxKind::Other, start..self.index, "expected a variable name after `$`"));
+                }
+
+                let position = self.position.advance(self.text, start);
+                let value = (self.lookup)(name, &position, len)?
+                    .ok_or_else(|| self.error(SyntaxKind::UndefinedVariable, start..self.index, format!("variable `${name}` is not defined")))?;
+                let number = parse_number(value.trim())
+                    .ok_or_else(|| self.error(SyntaxKind::Other, start..self.index, format!("variable `${name}` is `{}`, which is not a number", value.trim())))?;
+                Ok((number, start..self.index))
+            },
+            Some(c) => Err(self.error(SyntaxKind::Other, start..start + c.len_utf8(), format!("expected a number, found `{c}`"))),
+            None => Err(self.error(SyntaxKind::Other, start..start, "expected a number, found the end of the expression")),
+        }
+    }
+}
@@ -1,10 +1,24 @@
 mod de;
+mod diagnostic;
+mod env;
+mod escape;
+mod expr;
+mod parse;
+mod resolve;
+mod scalar;
 mod ser;
 mod types;
+mod variables;
 
-pub use de::from_str;
-pub use ser::{Serializer, to_string};
-pub use types::Vec2;
+pub use de::{Deserializer, from_str};
+pub use diagnostic::Diagnostic;
+pub use env::{Environment, SystemEnvironment};
+pub use expr::{eval, Number};
+pub use resolve::{FileSystem, Resolver, VirtualFileSystem};
+pub use ser::{BoolStyle, EnumTagging, Serializer, to_string, to_string_with};
+pub use types::{Color, Conditional, Gradient, Inferred, Value, Vec2, VarRef};
+
+use std::ops::Range;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -12,7 +26,70 @@ pub type Result<T> = std::result::Result<T, Error>;
 pub enum Error {
     Message(String),
     NotSupported(&'static str),
-    UnexpectedSequence(String)
+    UnexpectedSequence(String),
+    /// A key (or variable name) which hyprlang would not read back as the same key
+    InvalidKey(String),
+    /// A field of a struct that has no key in its category
+    MissingField(&'static str),
+    /// The text is not valid hyprlang, the kind tells what about it is wrong
+    Syntax(SyntaxKind, String),
+    /// A `source = path` could not be read
+    Include(String),
+    /// Any of the other errors, together with where in the text it happened
+    Located { location: Box<Location>, error: Box<Error> }
+}
+
+/// What about the text is not valid hyprlang
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntaxKind {
+    /// A `$variable` that is neither defined nor set in the environment
+    UndefinedVariable,
+    /// A `$variable` whose value refers back to the variable
+    RecursiveVariable,
+    /// A `{{` without a `}}` on the same line
+    UnclosedExpression,
+    /// A `(` within an expression without a `)`
+    UnclosedParenthesis,
+    /// A `category {` without a `}`
+    UnclosedCategory,
+    /// A `}` without a `category {`
+    UnmatchedBracket,
+    /// Anything else, such as a line that is not `key = value`
+    Other,
+}
+
+impl Error {
+    /// Where in the text the error happened, if it came from the deserializer
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            Error::Located { location, .. } => Some(location),
+            _ => None
+        }
+    }
+
+    /// The error without its location
+    pub fn inner(&self) -> &Error {
+        match self {
+            Error::Located { error, .. } => error,
+            error => error
+        }
+    }
+
+    /// Attaches the location, unless a more precise one is already attached
+    pub(crate) fn at(self, location: impl FnOnce() -> Location) -> Error {
+        match self {
+            Error::Located { .. } => self,
+            error => Error::Located { location: Box::new(location()), error: Box::new(error) }
+        }
+    }
+
+    /// Adds the key of the enclosing category to the front of the key path
+    pub(crate) fn in_key(mut self, key: &str) -> Error {
+        if let Error::Located { location, .. } = &mut self {
+            location.path = if location.path.is_empty() { key.to_string() } else { format!("{key}:{}", location.path) };
+        }
+        self
+    }
 }
 
 impl std::fmt::Display for Error {
@@ -26,11 +103,48 @@ impl std::fmt::Display for Error {
             Error::UnexpectedSequence(msg) => {
                 f.write_str("Unexpected Sequence: \n")?;
                 f.write_str(msg)
+            },
+            Error::InvalidKey(key) => write!(f, "`{key}` can not be written as a key"),
+            Error::MissingField(field) => write!(f, "missing field `{field}`"),
+            Error::Syntax(_, msg) => f.write_str(msg),
+            Error::Include(msg) => f.write_str(msg),
+            Error::Located { location, error } => {
+                write!(f, "{location}: {error}")?;
+                if !location.path.is_empty() {
+                    write!(f, " (in `{}`)", location.path)?;
+                }
+                Ok(())
             }
         }
     }
 }
 
+/// Where in the text something is
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Location {
+    /// The name of the file, if the deserializer was given one
+    pub file: Option<String>,
+    /// Starting at 1
+    pub line: usize,
+    /// In characters, starting at 1
+    pub column: usize,
+    /// In bytes, from the start of the file
+    pub span: Range<usize>,
+    /// The keys leading to the value, such as `decoration:blur:size`
+    pub path: String,
+    /// Whether the location is in a file read through `source =`, instead of the text handed to the deserializer
+    pub included: bool,
+}
+
+impl std::fmt::Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{file}:")?;
+        }
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
 impl serde::ser::Error for Error {
     fn custom<T>(msg:T) -> Self where T:std::fmt::Display {
         Error::Message(msg.to_string())
@@ -41,6 +155,10 @@ impl serde::de::Error for Error {
     fn custom<T>(msg:T) -> Self where T:std::fmt::Display {
         Error::Message(msg.to_string())
     }
+
+    fn missing_field(field: &'static str) -> Self {
+        Error::MissingField(field)
+    }
 }
 
 impl std::error::Error for Error {}
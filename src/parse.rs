@@ -0,0 +1,520 @@
+//! Turns hyprlang text into a tree of categories and values
+//!
+//! The deserializer does not walk the text directly, as hyprlang allows keys to repeat
+//! and categories to be reopened, so we first collect everything and then let serde pick
+
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::env::Environment;
+use crate::escape::unescape_hashes;
+use crate::resolve::{expand_glob, include_path, is_glob, Resolver};
+use crate::variables::Variables;
+use crate::{Error, Location, Result, SyntaxKind};
+
+pub(crate) struct Document<'s> {
+    pub(crate) root: Category,
+    pub(crate) variables: Variables<'s>,
+    pub(crate) warnings: Vec<Error>,
+}
+
+/// How the text is read, set through the [`Deserializer`](crate::Deserializer)
+#[derive(Default)]
+pub(crate) struct Settings {
+    /// Without one `source` is an ordinary key, as it is within categories
+    pub(crate) resolver: Option<Box<dyn Resolver>>,
+    /// A `source =` glob matching nothing is a warning instead of an error
+    pub(crate) allow_empty_globs: bool,
+    /// Defined before the text is read
+    pub(crate) variables: Vec<(String, String)>,
+    /// Without one `$name`s have to be defined in the text
+    pub(crate) environment: Option<Box<dyn Environment>>,
+    /// `$name`s found neither in the text nor the environment (if there is one) are kept, instead of failing
+    pub(crate) keep_unresolved: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub(crate) struct Category {
+    pub(crate) entries: Vec<Entry>,
+}
+
+impl Category {
+    /// The value of the last `key = value` line with this key
+    pub(crate) fn value_of(&self, key: &str) -> Option<&str> {
+        self.entries.iter().rev().find_map(|entry| match &entry.node {
+            Node::Value(value) if entry.key == key => Some(value.text.as_str()),
+            _ => None
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Entry {
+    pub(crate) key: String,
+    /// The `my-mouse` of a keyed special category `device[my-mouse] { }`
+    pub(crate) special_key: Option<String>,
+    /// Where the key starts
+    pub(crate) position: Position,
+    /// Within a `# hyprlang noerror true` region
+    pub(crate) noerror: bool,
+    pub(crate) node: Node,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Node {
+    Value(Value),
+    Category(Category),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Value {
+    /// With all variables expanded
+    pub(crate) text: String,
+    /// As written in the file (with the `##` still escaped)
+    pub(crate) raw: String,
+    /// Where the raw text starts
+    pub(crate) position: Position,
+}
+
+/// A point in the text
+#[derive(Debug, Clone, PartialEq, Default)]
+pub(crate) struct Position {
+    pub(crate) file: Option<Rc<str>>,
+    pub(crate) line: usize,
+    /// In characters, starting at 1
+    pub(crate) column: usize,
+    /// In bytes
+    pub(crate) offset: usize,
+    /// Where the text following this position continues on the next line, after a trailing `\`
+    pub(crate) joins: Vec<Join>,
+    /// In a file read through `source =`
+    pub(crate) included: bool,
+}
+
+/// A line continued from the previous one
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Join {
+    /// In bytes, from the position into the joined text
+    at: usize,
+    /// Where the continued text starts in the file
+    line: usize,
+    column: usize,
+    offset: usize,
+}
+
+impl Position {
+    /// The position `len` bytes further into `text`, which has to start at this position
+    ///
+    /// `text` is a single line, or lines joined by a trailing `\` which this position knows about
+    pub(crate) fn advance(&self, text: &str, len: usize) -> Position {
+        let crossed = self.joins.iter().take_while(|join| join.at <= len).count();
+        let joins = self.joins[crossed..].iter()
+            .map(|join| Join { at: join.at - len, ..join.clone() })
+            .collect();
+
+        match crossed.checked_sub(1).map(|last| &self.joins[last]) {
+            Some(join) => Position {
+                file: self.file.clone(),
+                line: join.line,
+                column: join.column + text[join.at..len].chars().count(),
+                offset: join.offset + len - join.at,
+                joins,
+                included: self.included
+            },
+            None => Position {
+                file: self.file.clone(),
+                line: self.line,
+                column: self.column + text[..len].chars().count(),
+                offset: self.offset + len,
+                joins,
+                included: self.included
+            }
+        }
+    }
+
+    /// Continues the text at `len` bytes from here with the text starting at `continued`
+    fn join(&mut self, len: usize, continued: &Position) {
+        self.joins.push(Join { at: len, line: continued.line, column: continued.column, offset: continued.offset });
+    }
+
+    /// The location of the `len` bytes starting here
+    ///
+    /// When they span multiple lines the span ends within the last one
+    pub(crate) fn location(&self, len: usize, path: impl Into<String>) -> Location {
+        let end = match self.joins.iter().rev().find(|join| join.at <= len) {
+            Some(join) => join.offset + len - join.at,
+            None => self.offset + len
+        };
+
+        Location {
+            file: self.file.as_deref().map(str::to_string),
+            line: self.line,
+            column: self.column,
+            span: self.offset..end,
+            path: path.into(),
+            included: self.included
+        }
+    }
+}
+
+struct OpenCategory {
+    key: String,
+    position: Position,
+    noerror: bool,
+    parent: Category,
+}
+
+/// A `# hyprlang if VAR` waiting for its `# hyprlang endif`
+struct Condition {
+    /// Whether this and all enclosing conditions hold
+    active: bool,
+    position: Position,
+}
+
+pub(crate) fn parse<'s>(text: &str, file: Option<&str>, settings: &'s Settings) -> Result<Document<'s>> {
+    read(text, file, settings, false)
+}
+
+/// Text that is a single value, every line of it is an entry with an empty key
+///
+/// Comments, line continuations, variables and expressions work as they do right of a `=`
+pub(crate) fn parse_value<'s>(text: &str, file: Option<&str>, settings: &'s Settings) -> Result<Document<'s>> {
+    read(text, file, settings, true)
+}
+
+fn read<'s>(text: &str, file: Option<&str>, settings: &'s Settings, single_value: bool) -> Result<Document<'s>> {
+    let mut parser = Parser {
+        open: vec![],
+        current: Category::default(),
+        variables: Variables::new(settings.environment.as_deref(), settings.keep_unresolved),
+        settings,
+        files: vec![],
+        conditions: vec![],
+        noerror: false,
+        single_value,
+        warnings: vec![]
+    };
+    for (name, value) in settings.variables.iter() {
+        parser.variables.define(name, value, Position::default());
+    }
+    parser.text(text, file, false)?;
+
+    Ok(Document { root: parser.current, variables: parser.variables, warnings: parser.warnings })
+}
+
+struct Parser<'s> {
+    open: Vec<OpenCategory>,
+    current: Category,
+    variables: Variables<'s>,
+    settings: &'s Settings,
+    /// The files currently being read, the innermost include last
+    files: Vec<PathBuf>,
+    conditions: Vec<Condition>,
+    /// Errors become warnings, set by `# hyprlang noerror true`
+    noerror: bool,
+    /// Every line is a value, see [`parse_value`]
+    single_value: bool,
+    warnings: Vec<Error>,
+}
+
+impl Parser<'_> {
+    /// Every file has to close the categories and conditions it opens, and `noerror` only lasts until its end
+    fn text(&mut self, text: &str, file: Option<&str>, included: bool) -> Result<()> {
+        let depth = self.open.len();
+        let conditions = self.conditions.len();
+        let noerror = self.noerror;
+        if let Some(file) = file {
+            self.files.push(PathBuf::from(file));
+        }
+
+        let res = self.lines(text, file, included, depth, conditions);
+
+        // Whatever the file left open is closed, so the including file can continue where it was
+        while self.open.len() > depth {
+            if let Some(OpenCategory { key, position, noerror, parent }) = self.open.pop() {
+                let category = std::mem::replace(&mut self.current, parent);
+                self.current.entries.push(nested(&key, position, noerror, Node::Category(category)));
+            }
+        }
+        self.conditions.truncate(conditions);
+        self.noerror = noerror;
+        if file.is_some() {
+            self.files.pop();
+        }
+
+        res
+    }
+
+    fn lines(&mut self, text: &str, file: Option<&str>, included: bool, depth: usize, conditions: usize) -> Result<()> {
+        let file: Option<Rc<str>> = file.map(Rc::from);
+        let mut offset = 0;
+        // The text of a line ending with `\` so far, without it, and where it starts
+        let mut continued: Option<(String, Position)> = None;
+
+        for (index, full_line) in text.split_inclusive('\n').enumerate() {
+            let start = Position { file: file.clone(), line: index + 1, column: 1, offset, joins: vec![], included };
+            offset += full_line.len();
+
+            let full_line = full_line.trim_end_matches(['\n', '\r']);
+            let res = if let Some(directive) = directive(full_line.trim()).filter(|_| continued.is_none()) {
+                let position = start.advance(full_line, full_line.len() - full_line.trim_start().len());
+                self.directive(directive, position, full_line.trim().len(), conditions)
+            } else if !self.active() {
+                continue;
+            } else {
+                let content = strip_comment(full_line);
+                let line = content.trim();
+                // where the trimmed line starts
+                let start = start.advance(content, content.len() - content.trim_start().len());
+
+                let (line, start) = match continued.take() {
+                    Some((mut joined, mut position)) => {
+                        position.join(joined.len(), &start);
+                        joined += line;
+                        (joined, position)
+                    },
+                    None => (line.to_string(), start)
+                };
+
+                if let Some(rest) = line.strip_suffix('\\') {
+                    continued = Some((rest.to_string(), start));
+                    continue;
+                }
+
+                if line.is_empty() { Ok(()) } else { self.line(&line, start, depth) }
+            };
+
+            res.or_else(|e| self.tolerate(e))?;
+        }
+
+        // A `\` on the last line continues with nothing
+        if let Some((line, start)) = continued {
+            let res = if line.trim().is_empty() { Ok(()) } else { self.line(line.trim_end(), start, depth) };
+            res.or_else(|e| self.tolerate(e))?;
+        }
+
+        if let Some(unclosed) = self.open.get(depth..).and_then(<[_]>::last) {
+            let msg = format!("category `{}` is never closed", unclosed.key);
+            let error = syntax(SyntaxKind::UnclosedCategory, &unclosed.position, unclosed.key.len(), path(&self.open, ""), msg);
+            self.tolerate(error)?;
+        }
+
+        if let Some(unclosed) = self.conditions.get(conditions..).and_then(<[_]>::last) {
+            let error = syntax(SyntaxKind::Other, &unclosed.position, 1, path(&self.open, ""), "`# hyprlang if` without a `# hyprlang endif`");
+            self.tolerate(error)?;
+        }
+
+        Ok(())
+    }
+
+    /// Within a `noerror` region the error becomes a warning, and the line is skipped
+    fn tolerate(&mut self, error: Error) -> Result<()> {
+        if self.noerror {
+            self.warnings.push(error);
+            Ok(())
+        } else {
+            Err(error)
+        }
+    }
+
+    /// Whether the lines are read, or skipped by a `# hyprlang if`
+    fn active(&self) -> bool {
+        self.conditions.last().is_none_or(|condition| condition.active)
+    }
+
+    /// `# hyprlang if VAR`, `# hyprlang if !VAR`, `# hyprlang endif` and `# hyprlang noerror true|false`,
+    /// others are left to hyprlang
+    ///
+    /// A variable holds if it is defined and not empty, `0`, `false`, `no` or `off`
+    fn directive(&mut self, directive: &str, position: Position, len: usize, conditions: usize) -> Result<()> {
+        let mut words = directive.split_whitespace();
+
+        match words.next() {
+            Some("if") => {
+                let Some(variable) = words.next() else {
+                    return Err(syntax(SyntaxKind::Other, &position, len, path(&self.open, ""), "expected a variable after `# hyprlang if`"));
+                };
+                let (negated, name) = match variable.strip_prefix('!') {
+                    Some(name) => (true, name),
+                    None => (false, variable)
+                };
+                let name = name.strip_prefix('$').unwrap_or(name);
+
+                let active = self.active() && self.holds(name, &position, len)? != negated;
+                self.conditions.push(Condition { active, position });
+            },
+            Some("endif") => {
+                if self.conditions.len() <= conditions {
+                    return Err(syntax(SyntaxKind::Other, &position, len, path(&self.open, ""), "`# hyprlang endif` without a `# hyprlang if`"));
+                }
+                self.conditions.pop();
+            },
+            Some("noerror") if self.active() => {
+                self.noerror = match words.next() {
+                    Some("true") => true,
+                    Some("false") => false,
+                    _ => return Err(syntax(SyntaxKind::Other, &position, len, path(&self.open, ""), "expected `true` or `false` after `# hyprlang noerror`"))
+                };
+            },
+            _ => ()
+        }
+
+        Ok(())
+    }
+
+    /// The value with its variables expanded, `raw` being what is right of the `=`
+    fn value(&self, raw: &str, position: Position, path: &str) -> Result<Value> {
+        let text = unescape_hashes(&self.variables.expand(raw, &position, path)?);
+        Ok(Value { text, raw: raw.to_string(), position })
+    }
+
+    fn holds(&self, name: &str, position: &Position, len: usize) -> Result<bool> {
+        let Some(value) = self.variables.value(name, position, len, &path(&self.open, ""))? else {
+            return Ok(false);
+        };
+
+        Ok(!matches!(value.trim().to_lowercase().as_str(), "" | "0" | "false" | "no" | "off"))
+    }
+
+    /// `start` is where the trimmed line starts
+    fn line(&mut self, line: &str, start: Position, depth: usize) -> Result<()> {
+        if self.single_value {
+            let value = self.value(line, start.clone(), "")?;
+            self.current.entries.push(nested("", start, self.noerror, Node::Value(value)));
+        } else if line == "}" {
+            if self.open.len() <= depth {
+                return Err(syntax(SyntaxKind::UnmatchedBracket, &start, 1, path(&self.open, ""), "closing bracket without an open category"));
+            }
+            // Just checked that there is one
+            let Some(OpenCategory { key, position, noerror, parent }) = self.open.pop() else {
+                return Ok(());
+            };
+
+            let category = std::mem::replace(&mut self.current, parent);
+            self.current.entries.push(nested(&key, position, noerror, Node::Category(category)));
+        } else if let Some((key, value)) = line.split_once('=') {
+            let raw = value.trim();
+            let position = start.advance(line, line.len() - value.trim_start().len());
+            let key = unescape_hashes(key.trim());
+
+            match key.strip_prefix('$') {
+                Some(name) => self.variables.define(name, raw, position),
+                None => {
+                    let value = self.value(raw, position, &path(&self.open, &key))?;
+
+                    match self.settings.resolver.as_deref() {
+                        Some(resolver) if key == "source" && self.open.is_empty() => self.include(resolver, &value.text, &value.position, raw.len())?,
+                        _ => self.current.entries.push(nested(&key, start, self.noerror, Node::Value(value)))
+                    }
+                }
+            }
+        } else if let Some(key) = line.strip_suffix('{') {
+            self.open.push(OpenCategory {
+                key: unescape_hashes(key.trim()),
+                position: start,
+                noerror: self.noerror,
+                parent: std::mem::take(&mut self.current)
+            });
+        } else {
+            return Err(syntax(SyntaxKind::Other, &start, line.len(), path(&self.open, ""), format!("expected `key = value`, `category {{` or `}}`, found `{line}`")));
+        }
+
+        Ok(())
+    }
+
+    /// Reads the files of a `source = path` line, as if their content was written in place of the line
+    ///
+    /// Globs include every match in sorted order
+    fn include(&mut self, resolver: &dyn Resolver, path: &str, position: &Position, len: usize) -> Result<()> {
+        let target = include_path(path, self.files.last().map(PathBuf::as_path), resolver);
+        let error = |msg: String| Error::Include(msg).at(|| position.location(len, "source"));
+
+        if !is_glob(&target) {
+            return self.include_file(resolver, &target, &error);
+        }
+
+        let matches = expand_glob(&target, resolver)
+            .map_err(|e| error(format!("can not expand `{}`: {e}", target.display())))?;
+
+        if matches.is_empty() {
+            let empty = error(format!("`{}` matches no file", target.display()));
+            if !self.settings.allow_empty_globs {
+                return Err(empty);
+            }
+            self.warnings.push(empty);
+        }
+
+        for file in matches {
+            self.include_file(resolver, &file, &error)?;
+        }
+        Ok(())
+    }
+
+    fn include_file(&mut self, resolver: &dyn Resolver, target: &Path, error: &dyn Fn(String) -> Error) -> Result<()> {
+        if self.files.iter().any(|file| file == target) {
+            let chain = self.files.iter().map(|file| format!("{} -> ", file.display())).collect::<String>();
+            return Err(error(format!("`{}` includes itself: {chain}{}", target.display(), target.display())));
+        }
+
+        let text = resolver.read(target)
+            .map_err(|e| error(format!("can not read `{}`: {e}", target.display())))?;
+
+        self.text(&text, Some(&target.display().to_string()), true)
+    }
+}
+
+/// `#` starts a comment, `##` is a literal `#` (for example in `col.active_border = rgb(33ccff) ##not a comment`)
+///
+/// The `##` are left in, so positions within the line stay the same
+pub(crate) fn strip_comment(line: &str) -> &str {
+    let mut chars = line.char_indices();
+
+    while let Some((index, c)) = chars.next() {
+        if c == '#' && chars.next().is_none_or(|(_, next)| next != '#') {
+            return &line[..index];
+        }
+    }
+
+    line
+}
+
+/// The text after `# hyprlang`
+fn directive(line: &str) -> Option<&str> {
+    let rest = line.strip_prefix('#')?.trim_start().strip_prefix("hyprlang")?;
+    rest.starts_with(char::is_whitespace).then(|| rest.trim())
+}
+
+/// The keys of all open categories, followed by the key
+fn path(open: &[OpenCategory], key: &str) -> String {
+    open.iter().map(|category| category.key.as_str())
+        .chain(Some(key).filter(|key| !key.is_empty()))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// `decoration:blur:size = 8` is the same as `size = 8` within `blur { }` within `decoration { }`,
+/// the deserializer merges categories of the same name, so both spellings end up in the same struct
+fn nested(path: &str, position: Position, noerror: bool, node: Node) -> Entry {
+    let mut segments = path.rsplit(':');
+    // rsplit always yields at least one segment
+    let (key, special_key) = special(segments.next().unwrap_or_default());
+
+    segments.fold(Entry { key, special_key, position: position.clone(), noerror, node }, |entry, segment| {
+        let (key, special_key) = special(segment);
+        Entry { key, special_key, position: position.clone(), noerror, node: Node::Category(Category { entries: vec![entry] }) }
+    })
+}
+
+/// Splits `device[my-mouse]` into `device` and `my-mouse`
+fn special(segment: &str) -> (String, Option<String>) {
+    let segment = segment.trim();
+
+    match segment.strip_suffix(']').and_then(|s| s.split_once('[')) {
+        Some((key, special_key)) => (key.trim().to_string(), Some(special_key.trim().to_string())),
+        None => (segment.to_string(), None)
+    }
+}
+
+fn syntax(kind: SyntaxKind, position: &Position, len: usize, path: String, msg: impl Into<String>) -> Error {
+    Error::Syntax(kind, msg.into()).at(|| position.location(len, path))
+}
@@ -0,0 +1,222 @@
+//! Where the files of `source = path` lines are read from
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// Reads the files included with `source = path`
+///
+/// The paths handed to the resolver are already absolute (or relative to the working directory,
+/// if the deserialized text had no file name), with `~` expanded and `.`/`..` removed
+pub trait Resolver {
+    /// The content of the file
+    fn read(&self, path: &Path) -> io::Result<String>;
+
+    /// What `~` expands to
+    fn home(&self) -> Option<PathBuf>;
+
+    /// The paths of everything within the directory, needed for globs such as `source = conf.d/*.conf`
+    fn list(&self, directory: &Path) -> io::Result<Vec<PathBuf>> {
+        let _ = directory;
+        Err(io::Error::new(io::ErrorKind::Unsupported, "this resolver can not list directories"))
+    }
+}
+
+/// Reads from the real filesystem
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileSystem;
+
+impl Resolver for FileSystem {
+    fn read(&self, path: &Path) -> io::Result<String> {
+        std::fs::read_to_string(path)
+    }
+
+    fn home(&self) -> Option<PathBuf> {
+        std::env::var_os("HOME").map(PathBuf::from)
+    }
+
+    fn list(&self, directory: &Path) -> io::Result<Vec<PathBuf>> {
+        std::fs::read_dir(directory)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect()
+    }
+}
+
+/// Files held in memory, for tests or configs that do not live on disk
+#[derive(Debug, Clone, Default)]
+pub struct VirtualFileSystem {
+    files: BTreeMap<PathBuf, String>,
+    home: Option<PathBuf>,
+}
+
+impl VirtualFileSystem {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a file, the path is normalized the same way as the included paths are
+    pub fn file(mut self, path: impl AsRef<Path>, text: &str) -> Self {
+        self.files.insert(normalize(path.as_ref()), text.to_string());
+        self
+    }
+
+    /// Sets what `~` expands to
+    pub fn home(mut self, path: impl AsRef<Path>) -> Self {
+        self.home = Some(path.as_ref().to_path_buf());
+        self
+    }
+}
+
+impl Resolver for VirtualFileSystem {
+    fn read(&self, path: &Path) -> io::Result<String> {
+        self.files.get(path)
+            .cloned()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no such file"))
+    }
+
+    fn home(&self) -> Option<PathBuf> {
+        self.home.clone()
+    }
+
+    /// Directories exist implicitly, through the files within them
+    fn list(&self, directory: &Path) -> io::Result<Vec<PathBuf>> {
+        let directory = normalize(directory);
+        let mut children = self.files.keys()
+            .filter_map(|path| path.strip_prefix(&directory).ok()?.components().next())
+            .map(|child| directory.join(child))
+            .collect::<Vec<_>>();
+        children.dedup();
+
+        Ok(children)
+    }
+}
+
+/// The path of a `source = path` line within the file `including`
+pub(crate) fn include_path(path: &str, including: Option<&Path>, resolver: &dyn Resolver) -> PathBuf {
+    let path = match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => match resolver.home() {
+            Some(home) => home.join(rest.trim_start_matches('/')),
+            None => PathBuf::from(path)
+        },
+        _ => PathBuf::from(path)
+    };
+
+    let path = match including.and_then(Path::parent) {
+        Some(directory) if path.is_relative() => directory.join(path),
+        _ => path
+    };
+
+    normalize(&path)
+}
+
+/// Whether the path contains any of the glob wildcards `*`, `?` or `[`
+pub(crate) fn is_glob(path: &Path) -> bool {
+    path.to_string_lossy().contains(['*', '?', '['])
+}
+
+/// Every path matching the glob, sorted
+///
+/// Wildcards only match within one component, and do not match a leading `.`
+pub(crate) fn expand_glob(pattern: &Path, resolver: &dyn Resolver) -> io::Result<Vec<PathBuf>> {
+    let mut paths = vec![PathBuf::new()];
+
+    for component in pattern.components() {
+        let Component::Normal(name) = component else {
+            paths.iter_mut().for_each(|path| path.push(component));
+            continue;
+        };
+
+        let name = name.to_string_lossy();
+        if !is_glob(Path::new(name.as_ref())) {
+            paths.iter_mut().for_each(|path| path.push(name.as_ref()));
+            continue;
+        }
+
+        let mut matches = vec![];
+        for directory in paths {
+            let listed = resolver.list(if directory.as_os_str().is_empty() { Path::new(".") } else { &directory });
+            let children = match listed {
+                Ok(children) => children,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e)
+            };
+
+            for child in children {
+                let Some(child_name) = child.file_name().map(|n| n.to_string_lossy().into_owned()) else {
+                    continue;
+                };
+                if !child_name.starts_with('.') && wildcard_match(&name, &child_name) {
+                    matches.push(directory.join(child_name));
+                }
+            }
+        }
+        paths = matches;
+    }
+
+    paths.sort();
+    paths.dedup();
+    Ok(paths)
+}
+
+/// `*` matches any text, `?` any character and `[a-z]` (or `[!a-z]`) one of (or none of) the characters
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+    matches_from(&pattern, &name)
+}
+
+fn matches_from(pattern: &[char], name: &[char]) -> bool {
+    match pattern.first() {
+        None => name.is_empty(),
+        Some('*') => (0..=name.len()).any(|skip| matches_from(&pattern[1..], &name[skip..])),
+        Some('?') => !name.is_empty() && matches_from(&pattern[1..], &name[1..]),
+        Some('[') => match class_match(&pattern[1..], name.first().copied()) {
+            Some((matched, len)) => matched && matches_from(&pattern[len + 1..], &name[1..]),
+            // Not a class (or nothing left to match), so a literal `[`
+            None => name.first() == Some(&'[') && matches_from(&pattern[1..], &name[1..]),
+        },
+        Some(c) => name.first() == Some(c) && matches_from(&pattern[1..], &name[1..]),
+    }
+}
+
+/// Whether the character is part of the class the pattern starts with (after the `[`),
+/// together with the length of the class including the `]`
+fn class_match(pattern: &[char], c: Option<char>) -> Option<(bool, usize)> {
+    let negated = matches!(pattern.first(), Some('!' | '^'));
+    let start = usize::from(negated);
+    // A `]` right at the start is part of the class
+    let end = start + 1 + pattern.get(start + 1..)?.iter().position(|c| *c == ']')?;
+    let class = &pattern[start..end];
+
+    let c = c?;
+    let mut found = false;
+    let mut index = 0;
+    while index < class.len() {
+        if class.get(index + 1) == Some(&'-') && index + 2 < class.len() {
+            found |= (class[index]..=class[index + 2]).contains(&c);
+            index += 3;
+        } else {
+            found |= class[index] == c;
+            index += 1;
+        }
+    }
+
+    Some((found != negated, end + 1))
+}
+
+/// Removes `.` and resolves `..` without touching the filesystem
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir if normalized.file_name().is_some() => {
+                normalized.pop();
+            },
+            component => normalized.push(component)
+        }
+    }
+
+    normalized
+}
@@ -0,0 +1,196 @@
+//! Booleans and numbers, read the way hyprlang's C++ parser reads them
+//!
+//! - booleans are `true`/`false`, `yes`/`no`, `on`/`off` (in any case) or an integer, which holds unless it is `0`
+//! - integers may be written in hex as `0x1f`, and `true`/`yes`/`on` are `1` (the others `0`)
+//! - floats may be written as integers
+//! - colors are integers, also written as `rgba(RRGGBBAA)` or `rgb(r, g, b)`, and gradients are colors followed by an angle
+//! - a vec2 is two numbers separated by a space
+//!
+//! The errors only describe what is wrong with the text, the deserializer adds what it expected
+
+use std::fmt::Display;
+use std::num::{IntErrorKind, ParseIntError};
+use std::ops::RangeInclusive;
+
+use crate::types::{Color, Gradient, Value, Vec2};
+
+pub(crate) fn parse_bool(text: &str) -> Result<bool, String> {
+    match text.to_lowercase().as_str() {
+        "true" | "yes" | "on" => Ok(true),
+        "false" | "no" | "off" => Ok(false),
+        _ => parse_integer(text)
+            .map(|int| int != 0)
+            .map_err(|_| "hyprlang booleans are `true`, `false`, `yes`, `no`, `on`, `off` or a number".to_string())
+    }
+}
+
+/// An integer in the range of `T`
+pub(crate) fn parse_int<T>(text: &str, range: RangeInclusive<T>) -> Result<T, String> where T: TryFrom<i128> + Display {
+    let out_of_range = || format!("out of range, it has to be between {} and {}", range.start(), range.end());
+
+    let int = match text.to_lowercase().as_str() {
+        "true" | "yes" | "on" => 1,
+        "false" | "no" | "off" => 0,
+        _ => parse_integer(text).map_err(|e| match e.kind() {
+            IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => out_of_range(),
+            _ => e.to_string()
+        })?
+    };
+
+    T::try_from(int).map_err(|_| out_of_range())
+}
+
+/// A float, which is out of range if it only became infinite by being too large
+pub(crate) fn parse_float(text: &str, max: f64) -> Result<f64, String> {
+    let float = text.parse::<f64>().map_err(|e| e.to_string())?;
+    let infinite = text.trim_start_matches(['-', '+']).to_lowercase().starts_with("inf");
+
+    if float.is_finite() && float.abs() > max || float.is_infinite() && !infinite {
+        return Err(format!("out of range, it has to be between {:e} and {max:e}", -max));
+    }
+    Ok(float)
+}
+
+/// What a value looks like without a type to read it as, in the order hyprlang tries them
+///
+/// Only the words are bools, a `1` is an integer. Integers beyond 64 bits are read as floats
+pub(crate) fn infer(text: &str) -> Value {
+    match text.to_lowercase().as_str() {
+        "true" | "yes" | "on" => return Value::Bool(true),
+        "false" | "no" | "off" => return Value::Bool(false),
+        _ => ()
+    }
+
+    if let Some(int) = parse_integer(text).ok().and_then(|int| i64::try_from(int).ok()) {
+        return Value::Int(int);
+    }
+    if let Some(float) = parse_number(text) {
+        return Value::Float(float);
+    }
+    if let Some(Ok(argb)) = color_function(text) {
+        return Value::Color(Color::from_argb(argb));
+    }
+    if let Some((x, y)) = text.split_once(' ').and_then(|(x, y)| Some((parse_number(x)?, parse_number(y)?))) {
+        return Value::Vec2(Vec2(x, y));
+    }
+    // A single color is a color (or an integer), not a gradient.
+    // Its colors have to be `rgba(..)` or `rgb(..)`, so numbers separated by spaces stay text
+    let is_color = |word: &str| word.ends_with("deg") || color_function(word).is_some();
+    if words(text).nth(1).is_some() && words(text).all(is_color) {
+        if let Ok((colors, angle)) = parse_gradient(text) {
+            return Value::Gradient(Gradient { colors: colors.into_iter().map(Color::from_argb).collect(), angle });
+        }
+    }
+
+    Value::String(text.to_string())
+}
+
+/// A color as hyprlang stores it, `0xAARRGGBB`
+///
+/// Written as `rgba(RRGGBBAA)`, `rgb(RRGGBB)`, `rgba(r, g, b, a)` with the alpha between 0 and 1,
+/// `rgb(r, g, b)`, or directly as that integer
+pub(crate) fn parse_color(text: &str) -> Result<u32, String> {
+    match color_function(text) {
+        Some(color) => color,
+        None => parse_int(text, 0..=u32::MAX)
+    }
+}
+
+/// The colors of a gradient, followed by an optional angle: `rgba(33ccffee) rgba(00ff99ee) 45deg`
+pub(crate) fn parse_gradient(text: &str) -> Result<(Vec<u32>, f64), String> {
+    let mut colors = vec![];
+    let mut angle = None;
+
+    for word in words(text) {
+        if angle.is_some() {
+            return Err(format!("`{word}` follows the angle, which has to be last"));
+        }
+
+        match word.strip_suffix("deg") {
+            Some(degrees) => angle = Some(parse_number(degrees).ok_or_else(|| format!("`{word}` is not an angle, such as `45deg`"))?),
+            None => colors.push(parse_color(word).map_err(|e| format!("`{word}` is not a color: {e}"))?)
+        }
+    }
+
+    if colors.is_empty() {
+        return Err("a gradient needs at least one color".to_string());
+    }
+    Ok((colors, angle.unwrap_or_default()))
+}
+
+/// `rgba(..)` or `rgb(..)`, `None` for any other text
+fn color_function(text: &str) -> Option<Result<u32, String>> {
+    let (alpha, args) = match text.strip_prefix("rgba(") {
+        Some(args) => (true, args),
+        None => (false, text.strip_prefix("rgb(")?)
+    };
+    let Some(args) = args.strip_suffix(')') else {
+        return Some(Err(format!("`{}(` is never closed", if alpha { "rgba" } else { "rgb" })));
+    };
+
+    if !args.contains(',') {
+        let digits = if alpha { 8 } else { 6 };
+        if args.len() != digits || !args.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Some(Err(format!("expected {digits} hex digits, found `{args}`")));
+        }
+        // Only hex digits, which fit into 32 bits
+        let int = u32::from_str_radix(args, 16).unwrap_or_default();
+        return Some(Ok(if alpha { int.rotate_right(8) } else { 0xff000000 | int }));
+    }
+
+    let parts: Vec<&str> = args.split(',').map(str::trim).collect();
+    if parts.len() != 3 + usize::from(alpha) {
+        return Some(Err(format!("expected {} channels, found {}", 3 + usize::from(alpha), parts.len())));
+    }
+
+    let mut channels = [0xff; 4];
+    for (channel, part) in channels.iter_mut().zip(&parts[..3]) {
+        match part.parse::<u8>() {
+            Ok(value) => *channel = value,
+            Err(_) => return Some(Err(format!("`{part}` is not a channel between 0 and 255")))
+        }
+    }
+    if alpha {
+        match parse_number(parts[3]).filter(|a| (0.0..=1.0).contains(a)) {
+            Some(a) => channels[3] = (a * 255.0).round() as u8,
+            None => return Some(Err(format!("`{}` is not an alpha between 0 and 1", parts[3])))
+        }
+    }
+
+    let [r, g, b, a] = channels.map(u32::from);
+    Some(Ok(a << 24 | r << 16 | g << 8 | b))
+}
+
+/// Splits at whitespace, except within parentheses, so `rgba(0, 0, 0, 1)` stays one word
+fn words(text: &str) -> impl Iterator<Item = &str> {
+    let mut depth = 0usize;
+    text.split(move |c: char| {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            _ => ()
+        }
+        c.is_whitespace() && depth == 0
+    }).filter(|word| !word.is_empty())
+}
+
+/// A decimal number, Rust would also read `inf` and `NaN`, which are words rather than numbers in a config
+fn parse_number(text: &str) -> Option<f64> {
+    let is_number = text.contains(|c: char| c.is_ascii_digit())
+        && text.chars().all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '-' | '+'));
+    text.parse().ok().filter(|_| is_number)
+}
+
+/// A decimal or `0x` hex integer, with an optional sign
+fn parse_integer(text: &str) -> Result<i128, ParseIntError> {
+    let (sign, digits) = match text.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", text.strip_prefix('+').unwrap_or(text))
+    };
+
+    match digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        // The sign is put back in front of the digits, so `-0x-1` stays invalid
+        Some(hex) if !hex.starts_with(['-', '+']) => i128::from_str_radix(&format!("{sign}{hex}"), 16),
+        _ => text.parse()
+    }
+}
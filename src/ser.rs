@@ -1,29 +1,283 @@
 use serde::{ser, Serialize};
 
+use crate::escape::{check_key, quote};
+use crate::types::{CONDITIONAL_TOKEN, VARREF_TOKEN};
+use crate::variables::is_name_char;
 use crate::{Error, Result};
 
 const SPACES_PER_TAB:usize = 4;
 
 pub fn to_string<T>(value: &T) -> Result<String> where T: Serialize {
-    let mut serial = Serializer {
-        output: String::new(),
-        fieldname: vec![],
-        field_prefix_needed: true,
-        within_tupple: 0
+    to_string_with(value, Serializer::new())
+}
+
+/// Same as [`to_string`], but with a configured [`Serializer`]
+pub fn to_string_with<T>(value: &T, mut serializer: Serializer) -> Result<String> where T: Serialize {
+    serializer.write_variables()?;
+    value.serialize(&mut serializer)?;
+
+    match serializer.wrap {
+        Some(width) => Ok(serializer.output.split_inclusive('\n').map(|line| wrap_line(line, width)).collect()),
+        None => Ok(serializer.output)
+    }
+}
+
+/// Continues the value of a `key = value` line longer than `width` characters on the next lines, with a trailing `\`
+///
+/// Continued lines are read without their indentation, so lines are only broken right after a space
+fn wrap_line(line: &str, width: usize) -> String {
+    let (line, newline) = match line.strip_suffix('\n') {
+        Some(line) => (line, "\n"),
+        None => (line, "")
+    };
+    let content = line.trim_start();
+    let Some(value_start) = line.find(" = ").map(|i| i + 3) else {
+        return format!("{line}{newline}");
     };
-    value.serialize(&mut serial)?;
-    Ok(serial.output)
+    if content.starts_with('#') || line.chars().count() <= width {
+        return format!("{line}{newline}");
+    }
+
+    let indent = format!("{}{}", &line[..line.len() - content.len()], " ".repeat(SPACES_PER_TAB));
+    let breaks = (value_start + 1..line.len())
+        .filter(|&i| line.is_char_boundary(i) && line[..i].ends_with(' ') && !line[i..].starts_with(char::is_whitespace))
+        .collect::<Vec<_>>();
+
+    let mut output = String::with_capacity(line.len() + newline.len());
+    let mut start = 0;
+    // The characters already on the current line
+    let mut used = 0;
+    loop {
+        if used + line[start..].chars().count() <= width {
+            break;
+        }
+
+        // The furthest break that still fits, or the closest one if none does
+        let candidates = breaks.iter().copied().filter(|&b| b > start);
+        let fitting = candidates.clone().take_while(|&b| used + line[start..b].chars().count() < width).last();
+        let Some(end) = fitting.or_else(|| candidates.clone().next()) else {
+            break;
+        };
+
+        output += &line[start..end];
+        output += "\\\n";
+        output += &indent;
+        used = indent.chars().count();
+        start = end;
+    }
+    output += &line[start..];
+    output += newline;
+
+    output
 }
 
 
 pub struct Serializer {
     output: String,
-    fieldname: Vec<&'static str>,
+    fieldname: Vec<String>,
     field_prefix_needed: bool,
-    within_tupple: usize
+    within_tupple: usize,
+
+    /// Length of fieldname when each of the currently open `name {` blocks was opened
+    blocks: Vec<usize>,
+    /// Every struct currently being serialized
+    categories: Vec<OpenStruct>,
+    seq_element: bool,
+    flat_ended: bool,
+    /// Every map currently being serialized
+    maps: Vec<OpenMap>,
+    /// Every [`Conditional`](crate::Conditional) currently being serialized
+    conditionals: Vec<OpenConditional>,
+    map_key: Option<String>,
+    /// Set by a field renamed to `device[name]`
+    special_key_field: Option<String>,
+    /// The `name = my-mouse` line to write first into the next category
+    special_key_line: Option<(String, String)>,
+
+    colon_paths: Option<usize>,
+    special_category_key: Option<String>,
+    variables: Vec<(String, String)>,
+    wrap: Option<usize>,
+    bool_style: BoolStyle,
+    keyword_variants: bool,
+    enum_tagging: EnumTagging,
+    /// How each tuple and struct variant currently being serialized was tagged
+    variants: Vec<EnumTagging>,
+    /// Length of the output and `within_tupple` right after the `key = ` of a sequence element was written,
+    /// so an enum can tell whether it is the element itself
+    keyword_element: Option<(usize, usize)>,
+}
+
+/// How the variant of an enum with data is written, unit variants are always just their name
+///
+/// The [`Deserializer`](crate::Deserializer) reads both tagged forms
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EnumTagging {
+    /// Only the data, `TuppleVarient(75, false)` becomes `75, false`, which can not be read back
+    ///
+    /// Reading such an enum back fails with "expected a single variant of `E`", use one of the tagged forms instead
+    #[default]
+    Untagged,
+    /// The variant is the first element of the value, `exec, kitty` or `movefocus, l`,
+    /// the fields of struct variants follow as `key: value`
+    FirstElement,
+    /// The variant is the only key within the field, `dispatcher:exec = kitty` or `layout { dwindle { } }`
+    ///
+    /// Within a tupple there are no keys, so the variant is the first element there
+    Key,
+}
+
+/// How bools are written, hyprlang reads all of them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BoolStyle {
+    /// `true` and `false`
+    #[default]
+    TrueFalse,
+    /// `yes` and `no`
+    YesNo,
+    /// `on` and `off`
+    OnOff,
+    /// `1` and `0`
+    Numeric,
+}
+
+struct OpenStruct {
+    block: bool,
+    /// The key field of a special category and the map key, which was already written in its place
+    skip_field: Option<(String, String)>,
+}
+
+struct OpenConditional {
+    /// `within_tupple` when it was started, to tell its fields apart from those of tupple structs within it
+    tupple_level: usize,
+    /// The `# hyprlang if` was written
+    started: bool,
+}
+
+struct OpenMap {
+    /// The serialized key and value of every entry
+    entries: Vec<(String, String)>,
+    /// The entries are written as special categories keyed by this field
+    key_field: Option<String>,
+    /// Structs with `#[serde(flatten)]` fields are maps of unknown length, their fields stay in order
+    sorted: bool,
+}
+
+impl Default for Serializer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Serializer {
+    pub fn new() -> Self {
+        Serializer {
+            output: String::new(),
+            fieldname: vec![],
+            field_prefix_needed: true,
+            within_tupple: 0,
+            blocks: vec![],
+            categories: vec![],
+            seq_element: false,
+            flat_ended: false,
+            maps: vec![],
+            conditionals: vec![],
+            map_key: None,
+            special_key_field: None,
+            special_key_line: None,
+            colon_paths: None,
+            special_category_key: None,
+            variables: vec![],
+            wrap: None,
+            bool_style: BoolStyle::default(),
+            keyword_variants: false,
+            enum_tagging: EnumTagging::default(),
+            variants: vec![],
+            keyword_element: None
+        }
+    }
+
+    /// Categories with at most `max_keys` keys are written as `category:key = value` lines instead of a block,
+    /// `usize::MAX` writes everything this way (which is what `hyprctl keyword` expects)
+    ///
+    /// Structs that are elements of a sequence are always written as a block, as otherwise they would merge
+    pub fn colon_paths(mut self, max_keys: usize) -> Self {
+        self.colon_paths = Some(max_keys);
+        self
+    }
+
+    /// Maps of structs are written as keyed special categories, so repeated `device { }` blocks
+    /// which contain the map key as `field` (for example `name = my-mouse`)
+    ///
+    /// This can also be done per field, by renaming it to `device[name]`
+    pub fn special_category_key(mut self, field: &str) -> Self {
+        self.special_category_key = Some(field.to_string());
+        self
+    }
+
+    /// Declares the variable `$name = value`, all variables are written before anything else,
+    /// so [`VarRef`](crate::VarRef)s can refer to them
+    pub fn variable(mut self, name: &str, value: &str) -> Self {
+        self.variables.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Values of lines longer than `width` characters are continued on the next line, after a trailing `\`
+    ///
+    /// Lines are only broken after a space, so a value without one stays on a single line
+    pub fn wrap(mut self, width: usize) -> Self {
+        self.wrap = Some(width);
+        self
+    }
+
+    /// Writes bools as `yes`/`no`, `on`/`off` or `1`/`0` instead of `true`/`false`
+    pub fn bool_style(mut self, style: BoolStyle) -> Self {
+        self.bool_style = style;
+        self
+    }
+
+    /// Enums within sequences are written with their variant as the keyword of the line,
+    /// instead of the name of the sequence
+    ///
+    /// A `Vec` of `Bind(..)`, `Exec(..)` and `Submap(..)` becomes `bind = ..`, `exec = ..` and `submap = ..` lines
+    /// in the order of the elements (with the variants renamed to lowercase).
+    /// Read them back with [`Deserializer::keyword_variants`](crate::Deserializer::keyword_variants)
+    ///
+    /// Only the sequence at the root is written this way, a sequence within a struct keeps its name.
+    /// serde can not `#[serde(flatten)]` a sequence, so keyword lines next to other settings are read twice:
+    /// once into the struct, and once into a `Vec` of the enum with a variant for every other key
+    pub fn keyword_variants(mut self) -> Self {
+        self.keyword_variants = true;
+        self
+    }
+
+    /// Writes the variant of enums with data, so they can be read back, see [`EnumTagging`]
+    pub fn enum_tagging(mut self, tagging: EnumTagging) -> Self {
+        self.enum_tagging = tagging;
+        self
+    }
+
+    fn write_variables(&mut self) -> Result<()> {
+        if self.variables.is_empty() {
+            return Ok(());
+        }
+
+        for (name, value) in std::mem::take(&mut self.variables) {
+            if name.is_empty() || !name.chars().all(is_name_char) {
+                return Err(Error::InvalidKey(format!("${name}")));
+            }
+
+            self.output += "$";
+            self.output += &name;
+            self.output += " = ";
+            ser::Serializer::serialize_str(&mut *self, &value)?;
+            self.output += "\n";
+        }
+        self.output += "\n";
+
+        Ok(())
+    }
+
     fn write_field_prefix(&mut self) {
         if !self.field_prefix_needed {
             self.field_prefix_needed = true;
@@ -31,20 +285,22 @@ impl Serializer {
         }
 
         self.indent();
-        self.output += match self.fieldname.last() {
-            Some(current) => current,
-            None => ""
-        };
+        let path_start = self.blocks.last().copied().unwrap_or_default();
+        if let Some(path) = self.fieldname.get(path_start..) {
+            self.output += &path.join(":");
+        }
         self.output += " = "
     }
 
-    fn add_fieldname(&mut self, name: &'static str) {
-        self.fieldname.push(name);
+    fn add_fieldname(&mut self, name: &str) -> Result<()> {
+        check_key(name)?;
+        self.fieldname.push(name.to_string());
         self.field_prefix_needed = true;
         
         self.write_field_prefix();
 
         self.field_prefix_needed = false;
+        Ok(())
     }
 
     fn pop_fieldname(&mut self) {
@@ -53,7 +309,7 @@ impl Serializer {
     }
     
     fn indent(&mut self) {
-        for _ in 1..self.fieldname.len() {
+        for _ in 0..self.blocks.len() {
             // Yes, we relly on the compiler to hopefully optimize the inner for loop away
             for _ in 0..SPACES_PER_TAB {
                 self.output += " ";
@@ -91,12 +347,22 @@ impl Serializer {
         Ok(())
     }
 
-    fn struct_start(&mut self) -> Result<()> {
+    fn struct_start(&mut self, len: usize) -> Result<()> {
+        let seq_element = std::mem::take(&mut self.seq_element);
+
+        let skip_field = self.special_key_line.clone();
+
         if self.fieldname.is_empty() {
             // This is the root object, so no closure and indentation
+            self.categories.push(OpenStruct { block: false, skip_field: None });
         } else {
             if self.is_in_tupple() {
                 // the struct is within a tupple
+                self.categories.push(OpenStruct { block: false, skip_field: None });
+            } else if !seq_element && self.colon_paths.is_some_and(|max| len <= max) {
+                // the keys are prefixed with the path instead, so we remove the `name = `
+                self.remove_field_prefix();
+                self.categories.push(OpenStruct { block: false, skip_field: None });
             } else {
                 // standard serialization as a category
                 if let Some(trimmed) = self.output.strip_suffix("= ") {
@@ -106,17 +372,29 @@ impl Serializer {
                 }
 
                 self.output += "{\n";
+                self.blocks.push(self.fieldname.len());
+
+                if let Some((field, key)) = self.special_key_line.take() {
+                    self.struct_element(&field, &key)?;
+                }
+                self.categories.push(OpenStruct { block: true, skip_field });
             }
         }
 
         Ok(())
     }
 
-    fn struct_element<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    fn remove_field_prefix(&mut self) {
+        let prefix = self.output.rfind('\n').map(|i| i + 1).unwrap_or_default();
+        self.output.truncate(prefix);
+    }
+
+    fn struct_element<T>(&mut self, key: &str, value: &T) -> Result<()>
         where
             T: ?Sized + Serialize {
 
         if self.is_in_tupple() {
+            check_key(key)?;
             self.output += key;
             self.output += ": ";
             
@@ -125,11 +403,30 @@ impl Serializer {
             self.output += ", ";
 
 
+        } else if let Some((_, map_key)) = self.categories.last().and_then(|open| open.skip_field.as_ref()).filter(|(field, _)| field == key) {
+            // Already written from the map key, which has to agree with the field
+            let field = value.serialize(MapKeySerializer).ok();
+            if field.as_ref() != Some(map_key) {
+                let field = field.unwrap_or_default();
+                return Err(Error::Message(format!("the `{key}` field is `{field}`, but the map key of its category is `{map_key}`")));
+            }
         } else {
-            self.add_fieldname(key);
+            let key = match key.split_once('[').and_then(|(key, rest)| Some((key, rest.strip_suffix(']')?))) {
+                Some((key, key_field)) => {
+                    self.special_key_field = Some(key_field.to_string());
+                    key
+                },
+                None => key
+            };
+
+            self.add_fieldname(key)?;
             
-            value.serialize(&mut *self)?;
-            self.output += "\n";
+            let res = value.serialize(&mut *self);
+            self.special_key_field = None;
+            res?;
+            if !std::mem::take(&mut self.flat_ended) {
+                self.output += "\n";
+            }
 
             self.pop_fieldname();   
         }
@@ -137,6 +434,8 @@ impl Serializer {
     }
 
     fn struct_end(&mut self) -> Result<()> {
+        let block = self.categories.pop().is_some_and(|open| open.block);
+
         if self.is_in_tupple() {
             // We would otherwise produce an extra ,
             // as the tupple containing this struct will also append one
@@ -145,18 +444,217 @@ impl Serializer {
             } else {
                 return Err(Error::UnexpectedSequence(self.output.clone()))
             }
-        } else if !self.fieldname.is_empty() {
+        } else if block {
             // We have to close this category
+            self.blocks.pop();
             self.indent();
             self.output += "}";
             self.output += "\n";
+        } else if !self.fieldname.is_empty() {
+            // Written as colon paths, every key already ended its line
+            self.flat_ended = true;
+        }
+
+        Ok(())
+    }
+
+    fn map_start(&mut self, len: Option<usize>) -> Result<()> {
+        if self.is_in_tupple() {
+            return Err(Error::NotSupported("A map contained inside a Tupple"));
+        }
+
+        let key_field = self.special_key_field.take().or_else(|| self.special_category_key.clone());
+
+        if key_field.is_some() && !self.fieldname.is_empty() {
+            // Every entry becomes its own category named like the map
+            self.remove_field_prefix();
+            self.field_prefix_needed = true;
+            self.categories.push(OpenStruct { block: false, skip_field: None });
+        } else {
+            // Without a length we assume it is large
+            self.struct_start(len.unwrap_or(usize::MAX))?;
+        }
+
+        self.maps.push(OpenMap { entries: vec![], key_field, sorted: len.is_some() });
+        Ok(())
+    }
+
+    fn map_key<T>(&mut self, key: &T) -> Result<()>
+        where T: ?Sized + Serialize {
+        self.map_key = Some(key.serialize(MapKeySerializer)?);
+        Ok(())
+    }
+
+    /// The entry is written into its own buffer, so we can sort them once the map is done
+    /// (otherwise HashMaps would produce a different output every time)
+    fn map_value<T>(&mut self, value: &T) -> Result<()>
+        where T: ?Sized + Serialize {
+        let Some(key) = self.map_key.take() else {
+            return Err(Error::UnexpectedSequence("Map value without a key".to_string()));
+        };
+
+        let key_field = self.maps.last().and_then(|map| map.key_field.clone());
+
+        let output = std::mem::take(&mut self.output);
+        let res = match key_field {
+            Some(field) if !self.fieldname.is_empty() => self.special_category(field, &key, value),
+            _ => self.struct_element(&key, value)
+        };
+        let entry = std::mem::replace(&mut self.output, output);
+        res?;
+
+        if let Some(map) = self.maps.last_mut() {
+            map.entries.push((key, entry));
+        }
+        Ok(())
+    }
+
+    fn special_category<T>(&mut self, field: String, key: &str, value: &T) -> Result<()>
+        where T: ?Sized + Serialize {
+        self.write_field_prefix();
+        self.special_key_line = Some((field, key.to_string()));
+        self.seq_element = true;
+
+        let res = value.serialize(&mut *self);
+        self.seq_element = false;
+        res?;
+
+        if self.special_key_line.take().is_none() {
+            self.output += "\n";
+            Ok(())
+        } else {
+            // Not a struct, so there was no category to put the key into.
+            // Instead we write it as a normal entry, `device:key = value`
+            self.output.clear();
+            self.struct_element(key, value)
+        }
+    }
+
+    /// Writes the variant as the key of the line, if the enum is an element of a sequence in keyword mode
+    fn keyword_variant(&mut self, variant: &str) -> Result<bool> {
+        if self.keyword_element.take() != Some((self.output.len(), self.within_tupple)) {
+            return Ok(false);
+        }
+        check_key(variant)?;
+
+        // The variant takes the place of the name of the sequence, until the element ends
+        self.remove_field_prefix();
+        match self.fieldname.last_mut() {
+            Some(name) => *name = variant.to_string(),
+            None => self.fieldname.push(variant.to_string())
+        }
+        self.field_prefix_needed = true;
+        self.write_field_prefix();
+
+        Ok(true)
+    }
+
+    /// How the variant is tagged, after the variant got the chance to become the keyword of the line
+    fn variant_tagging(&mut self, variant: &str) -> Result<EnumTagging> {
+        if self.keyword_variant(variant)? {
+            return Ok(EnumTagging::Untagged);
+        }
+
+        Ok(match self.enum_tagging {
+            EnumTagging::Key if self.is_in_tupple() => EnumTagging::FirstElement,
+            tagging => tagging
+        })
+    }
+
+    /// Opens the variant as the only key of the field (a category, or a colon path)
+    fn keyed_variant_start(&mut self, variant: &str) -> Result<()> {
+        self.struct_start(1)?;
+        self.add_fieldname(variant)
+    }
+
+    fn keyed_variant_end(&mut self) -> Result<()> {
+        if !std::mem::take(&mut self.flat_ended) {
+            self.output += "\n";
+        }
+        self.pop_fieldname();
+        self.struct_end()
+    }
+
+    fn is_conditional_field(&self) -> bool {
+        self.conditionals.last().is_some_and(|open| open.tupple_level == self.within_tupple)
+    }
+
+    /// The first field is the condition, the second the value
+    fn conditional_field<T>(&mut self, value: &T) -> Result<()>
+        where T: ?Sized + Serialize {
+        let Some(open) = self.conditionals.last_mut() else {
+            return Err(Error::UnexpectedSequence(self.output.clone()));
+        };
+
+        if std::mem::replace(&mut open.started, true) {
+            return value.serialize(&mut *self);
+        }
+
+        let condition = value.serialize(MapKeySerializer)?;
+        let name = condition.strip_prefix('!').unwrap_or(&condition);
+        if name.is_empty() || !name.chars().all(is_name_char) {
+            return Err(Error::InvalidKey(condition));
+        }
+
+        // The `key = ` of the line was already written, so it is moved below the directive
+        let line_start = self.output.rfind('\n').map(|i| i + 1).unwrap_or_default();
+        let prefix = self.output.split_off(line_start);
+        self.indent();
+        self.output += "# hyprlang if ";
+        self.output += &condition;
+        self.output += "\n";
+        self.output += &prefix;
+
+        Ok(())
+    }
+
+    fn conditional_end(&mut self) -> Result<()> {
+        self.conditionals.pop();
+
+        // Blocks and colon paths already ended their line, values did not
+        if !std::mem::take(&mut self.flat_ended) && !self.output.ends_with('\n') {
+            self.output += "\n";
         }
+        self.indent();
+        self.output += "# hyprlang endif";
 
+        // Fields end their line afterwards, the root has nobody to do that
+        if self.fieldname.is_empty() {
+            self.output += "\n";
+        }
         Ok(())
     }
+
+    fn map_end(&mut self) -> Result<()> {
+        let (mut entries, sorted) = self.maps.pop().map(|map| (map.entries, map.sorted)).unwrap_or_default();
+        if sorted {
+            entries.sort_by(|(a, _), (b, _)| key_order(a, b));
+        }
+
+        for (_, entry) in entries {
+            self.output += &entry;
+        }
+
+        self.struct_end()
+    }
+}
+
+/// Numeric keys come first, in the order of their numbers (`2` before `10`), followed by all other keys
+fn key_order(a: &str, b: &str) -> std::cmp::Ordering {
+    let number = |key: &str| {
+        let numeric = key.bytes().all(|c| c.is_ascii_digit() || b"+-.eE".contains(&c));
+        key.parse::<f64>().ok().filter(|_| numeric)
+    };
+
+    match (number(a), number(b)) {
+        (Some(x), Some(y)) => x.total_cmp(&y).then_with(|| a.cmp(b)),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => a.cmp(b)
+    }
 }
 
-impl<'a> ser::Serializer for &'a mut Serializer {
+impl ser::Serializer for &mut Serializer {
     type Ok = ();
     type Error = Error;
 
@@ -212,7 +710,16 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     }
 
     fn serialize_bool(self, v: bool) -> std::result::Result<Self::Ok, Self::Error> {
-        self.output += if v { "true" } else { "false" };
+        self.output += match (self.bool_style, v) {
+            (BoolStyle::TrueFalse, true) => "true",
+            (BoolStyle::TrueFalse, false) => "false",
+            (BoolStyle::YesNo, true) => "yes",
+            (BoolStyle::YesNo, false) => "no",
+            (BoolStyle::OnOff, true) => "on",
+            (BoolStyle::OnOff, false) => "off",
+            (BoolStyle::Numeric, true) => "1",
+            (BoolStyle::Numeric, false) => "0",
+        };
         Ok(())
     }
 
@@ -221,13 +728,12 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     }
 
     fn serialize_str(self, v: &str) -> std::result::Result<Self::Ok, Self::Error> {
-        if v.contains(',') {
-            self.output += "\"";            
-            self.output += v;            
-            self.output += "\"";            
-        } else {
-            self.output += v;
+        if v.is_empty() && self.seq_element && !self.is_in_tupple() {
+            // A lone `key = ` is an empty sequence
+            self.output += "\"\"";
+            return Ok(());
         }
+        self.output += &quote(v);
 
         Ok(())
     }
@@ -264,18 +770,30 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         ) -> std::result::Result<Self::Ok, Self::Error> {
         // Unit Variant is a "Classic" Enum, aka without data
 
-        self.output += variant;
+        if !self.keyword_variant(variant)? {
+            self.output += variant;
+        }
         Ok(())
     }
 
     // Newtype are single field types, aka wrappers
     fn serialize_newtype_struct<T>(
             self,
-            _name: &'static str,
+            name: &'static str,
             value: &T,
         ) -> std::result::Result<Self::Ok, Self::Error>
         where
             T: ?Sized + Serialize {
+        if name == VARREF_TOKEN {
+            // The `$name` is written as is, so it is expanded when read
+            let reference = value.serialize(MapKeySerializer)?;
+            let name = reference.strip_prefix('$').unwrap_or_default();
+            if name.is_empty() || !name.chars().all(is_name_char) {
+                return Err(Error::InvalidKey(reference));
+            }
+            self.output += &reference;
+            return Ok(());
+        }
         value.serialize(self)
     }
 
@@ -283,13 +801,26 @@ impl<'a> ser::Serializer for &'a mut Serializer {
             self,
             _name: &'static str,
             _variant_index: u32,
-            _variant: &'static str,
+            variant: &'static str,
             value: &T,
         ) -> std::result::Result<Self::Ok, Self::Error>
         where
             T: ?Sized + Serialize {
         
-        value.serialize(self)
+        match self.variant_tagging(variant)? {
+            EnumTagging::Untagged => value.serialize(self),
+            EnumTagging::FirstElement => {
+                self.tupple_start()?;
+                self.tupple_element(variant)?;
+                self.tupple_element(value)?;
+                self.tupple_end()
+            },
+            EnumTagging::Key => {
+                self.keyed_variant_start(variant)?;
+                value.serialize(&mut *self)?;
+                self.keyed_variant_end()
+            }
+        }
     }
 
     fn serialize_seq(self, _len: Option<usize>) -> std::result::Result<Self::SerializeSeq, Self::Error> {
@@ -306,11 +837,18 @@ impl<'a> ser::Serializer for &'a mut Serializer {
 
     fn serialize_tuple_struct(
             self,
-            _name: &'static str,
+            name: &'static str,
             _len: usize,
         ) -> std::result::Result<Self::SerializeTupleStruct, Self::Error> {
 
-        self.tupple_start()?;
+        if name == CONDITIONAL_TOKEN {
+            if self.is_in_tupple() {
+                return Err(Error::NotSupported("A Conditional contained inside a Tupple"));
+            }
+            self.conditionals.push(OpenConditional { tupple_level: self.within_tupple, started: false });
+        } else {
+            self.tupple_start()?;
+        }
         Ok(self)
     }
 
@@ -318,21 +856,30 @@ impl<'a> ser::Serializer for &'a mut Serializer {
             self,
             _name: &'static str,
             _variant_index: u32,
-            _variant: &'static str,
+            variant: &'static str,
             _len: usize,
         ) -> std::result::Result<Self::SerializeTupleVariant, Self::Error> {
         
+        let tagging = self.variant_tagging(variant)?;
+        if tagging == EnumTagging::Key {
+            self.keyed_variant_start(variant)?;
+        }
+        self.variants.push(tagging);
+
         self.tupple_start()?;
+        if tagging == EnumTagging::FirstElement {
+            self.tupple_element(variant)?;
+        }
         Ok(self)
     }
 
     fn serialize_struct(
             self,
             _name: &'static str,
-            _len: usize,
+            len: usize,
         ) -> std::result::Result<Self::SerializeStruct, Self::Error> {
         
-        self.struct_start()?;
+        self.struct_start(len)?;
         Ok(self)
     }
 
@@ -341,18 +888,32 @@ impl<'a> ser::Serializer for &'a mut Serializer {
             self,
             _name: &'static str,
             _variant_index: u32,
-            _variant: &'static str,
-            _len: usize,
+            variant: &'static str,
+            len: usize,
         ) -> std::result::Result<Self::SerializeStructVariant, Self::Error> {
 
-        self.struct_start()?;
+        let tagging = self.variant_tagging(variant)?;
+        self.variants.push(tagging);
+
+        match tagging {
+            EnumTagging::Untagged => (),
+            EnumTagging::FirstElement => {
+                self.tupple_start()?;
+                self.tupple_element(variant)?;
+            },
+            EnumTagging::Key => {
+                self.keyed_variant_start(variant)?;
+                self.seq_element = true;
+            }
+        }
+        self.struct_start(len)?;
         Ok(self)
     }
 
-    fn serialize_map(self, _len: Option<usize>) -> std::result::Result<Self::SerializeMap, Self::Error> {
-        // We use this to serialize things such as class, title etc in windowrules
-        
-        Err(Error::NotSupported("Maps"))
+    // Maps are serialized like structs, so as categories
+    fn serialize_map(self, len: Option<usize>) -> std::result::Result<Self::SerializeMap, Self::Error> {
+        self.map_start(len)?;
+        Ok(self)
     }
 
     fn collect_str<T>(self, value: &T) -> std::result::Result<Self::Ok, Self::Error>
@@ -364,7 +925,7 @@ impl<'a> ser::Serializer for &'a mut Serializer {
 }
 
 // We serialize sequences as the same fieldname/key multiple times
-impl<'a> ser::SerializeSeq for &'a mut Serializer {
+impl ser::SerializeSeq for &mut Serializer {
     type Ok = ();
     type Error = Error;
 
@@ -376,7 +937,22 @@ impl<'a> ser::SerializeSeq for &'a mut Serializer {
 
         } else {
             self.write_field_prefix();
-            value.serialize(&mut **self)?;
+            self.seq_element = true;
+
+            // Only the sequence at the root has no name, which the deserializer reads keywords from
+            let keyword = self.keyword_variants && self.fieldname.is_empty();
+            let fieldname = keyword.then(|| self.fieldname.clone());
+            if keyword {
+                self.keyword_element = Some((self.output.len(), self.within_tupple));
+            }
+            let res = value.serialize(&mut **self);
+            self.keyword_element = None;
+            if let Some(fieldname) = fieldname {
+                self.fieldname = fieldname;
+            }
+            res?;
+
+            self.seq_element = false;
             self.output += "\n";
         }
         
@@ -396,41 +972,30 @@ impl<'a> ser::SerializeSeq for &'a mut Serializer {
     }
 }
 
-impl<'a> ser::SerializeMap for &'a mut Serializer {
+impl ser::SerializeMap for &mut Serializer {
     type Ok = ();
     type Error = Error;
 
-    fn serialize_key<T>(&mut self, _key: &T) -> std::result::Result<(), Self::Error>
+    fn serialize_key<T>(&mut self, key: &T) -> std::result::Result<(), Self::Error>
         where
             T: ?Sized + Serialize {
-        
-        Err(Error::NotSupported("Maps"))
+        self.map_key(key)
     }
 
-    fn serialize_value<T>(&mut self, _value: &T) -> std::result::Result<(), Self::Error>
+    fn serialize_value<T>(&mut self, value: &T) -> std::result::Result<(), Self::Error>
         where
             T: ?Sized + Serialize {
-        
-        Err(Error::NotSupported("Maps"))
+        self.map_value(value)
     }
 
-    fn serialize_entry<K, V>(&mut self, _key: &K, _value: &V) -> std::result::Result<(), Self::Error>
-        where
-            K: ?Sized + Serialize,
-            V: ?Sized + Serialize, {
-        
-        Err(Error::NotSupported("Maps"))
-    }
-
-
     fn end(self) -> std::result::Result<Self::Ok, Self::Error> {
-        Err(Error::NotSupported("Maps"))
+        self.map_end()
     }
 }
 
 // Structs are serialized as categories
 // Except if they are within tupples
-impl<'a> ser::SerializeStruct for &'a mut Serializer {
+impl ser::SerializeStruct for &mut Serializer {
     type Ok = ();
     type Error = Error;
 
@@ -445,7 +1010,7 @@ impl<'a> ser::SerializeStruct for &'a mut Serializer {
     }
 }
 
-impl<'a> ser::SerializeStructVariant for &'a mut Serializer {
+impl ser::SerializeStructVariant for &mut Serializer {
     type Ok = ();
     type Error = Error;
 
@@ -456,13 +1021,23 @@ impl<'a> ser::SerializeStructVariant for &'a mut Serializer {
     }
 
     fn end(self) -> std::result::Result<Self::Ok, Self::Error> {
-        self.struct_end()
+        self.struct_end()?;
+
+        match self.variants.pop() {
+            Some(EnumTagging::FirstElement) => {
+                // The struct removed the `, ` the tupple expects after its last element
+                self.output += ", ";
+                self.tupple_end()
+            },
+            Some(EnumTagging::Key) => self.keyed_variant_end(),
+            _ => Ok(())
+        }
     }
 
 }
 
 // Tupples are always sequences behind a single key/fieldname
-impl<'a> ser::SerializeTuple for &'a mut Serializer {
+impl ser::SerializeTuple for &mut Serializer {
     type Ok = ();
     type Error = Error;
 
@@ -478,7 +1053,7 @@ impl<'a> ser::SerializeTuple for &'a mut Serializer {
     }
 }
 
-impl<'a> ser::SerializeTupleStruct for &'a mut Serializer {
+impl ser::SerializeTupleStruct for &mut Serializer {
     type Ok = ();
     type Error = Error;
 
@@ -486,15 +1061,23 @@ impl<'a> ser::SerializeTupleStruct for &'a mut Serializer {
         where
             T: ?Sized + Serialize {
         
-        self.tupple_element(value)
+        if self.is_conditional_field() {
+            self.conditional_field(value)
+        } else {
+            self.tupple_element(value)
+        }
     }
 
     fn end(self) -> std::result::Result<Self::Ok, Self::Error> {
-        self.tupple_end()
+        if self.is_conditional_field() {
+            self.conditional_end()
+        } else {
+            self.tupple_end()
+        }
     }
 }
 
-impl<'a> ser::SerializeTupleVariant for &'a mut Serializer {
+impl ser::SerializeTupleVariant for &mut Serializer {
     type Ok = ();
     type Error = Error;
 
@@ -506,7 +1089,152 @@ impl<'a> ser::SerializeTupleVariant for &'a mut Serializer {
     }
 
     fn end(self) -> std::result::Result<Self::Ok, Self::Error> {
-        self.tupple_end()
+        self.tupple_end()?;
+
+        match self.variants.pop() {
+            Some(EnumTagging::Key) => self.keyed_variant_end(),
+            _ => Ok(())
+        }
     }
 }
 
+
+/// Map keys become the key of a line or the name of a category, so only things that fit there are allowed
+struct MapKeySerializer;
+
+impl MapKeySerializer {
+    fn unsupported() -> Error {
+        Error::NotSupported("Map keys that are not strings, numbers, bools or unit enums")
+    }
+}
+
+impl ser::Serializer for MapKeySerializer {
+    type Ok = String;
+    type Error = Error;
+
+    type SerializeSeq = ser::Impossible<String, Error>;
+    type SerializeTuple = ser::Impossible<String, Error>;
+    type SerializeTupleStruct = ser::Impossible<String, Error>;
+    type SerializeTupleVariant = ser::Impossible<String, Error>;
+    type SerializeMap = ser::Impossible<String, Error>;
+    type SerializeStruct = ser::Impossible<String, Error>;
+    type SerializeStructVariant = ser::Impossible<String, Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<String> {
+        Err(Self::unsupported())
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<String> {
+        Err(Self::unsupported())
+    }
+
+    fn serialize_char(self, v: char) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<String> {
+        Err(Self::unsupported())
+    }
+
+    fn serialize_none(self) -> Result<String> {
+        Err(Self::unsupported())
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<String>
+        where
+            T: ?Sized + Serialize {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<String> {
+        Err(Self::unsupported())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<String> {
+        Err(Self::unsupported())
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _variant_index: u32, variant: &'static str) -> Result<String> {
+        Ok(variant.to_string())
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<String>
+        where
+            T: ?Sized + Serialize {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(self, _name: &'static str, _variant_index: u32, _variant: &'static str, _value: &T) -> Result<String>
+        where
+            T: ?Sized + Serialize {
+        Err(Self::unsupported())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(Self::unsupported())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(Self::unsupported())
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct> {
+        Err(Self::unsupported())
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, _variant_index: u32, _variant: &'static str, _len: usize) -> Result<Self::SerializeTupleVariant> {
+        Err(Self::unsupported())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(Self::unsupported())
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Err(Self::unsupported())
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, _variant_index: u32, _variant: &'static str, _len: usize) -> Result<Self::SerializeStructVariant> {
+        Err(Self::unsupported())
+    }
+}
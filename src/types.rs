@@ -1,9 +1,12 @@
 //! hyprlang specific types, such as color, vec2 and gradient
 
-use serde::{de::Visitor, Deserialize, Serialize};
+use serde::{de::{IntoDeserializer, Visitor}, ser::SerializeTupleStruct, Deserialize, Serialize};
+
+use crate::scalar::{infer, parse_color, parse_gradient};
 
 /// Serves to provide a 2 dimensional vector  
-/// It is serialized as a string with the two numbers deliminated by a space
+/// It is serialized as a string with the two numbers deliminated by a space,
+/// and deserialized from such a string or a sequence of two numbers
 #[derive(Debug, Clone, PartialEq, Default)]
 pub struct Vec2(pub f64, pub f64);
 
@@ -41,7 +44,18 @@ impl<'de> Deserialize<'de> for Vec2 {
                         (Err(e), Ok(_)) => Err(E::custom(format!("Parse failed on first value: {e}"))),
                     }
                 } else {
-                    Err(E::custom(format!("Invalid formating")))
+                    Err(E::custom("Invalid formating"))
+                }
+            }
+
+            // Formats which write the vec2 as a sequence of two numbers
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+                where
+                    A: serde::de::SeqAccess<'de>, {
+                match (seq.next_element()?, seq.next_element()?) {
+                    (Some(x), Some(y)) => Ok(Vec2(x, y)),
+                    (Some(_), None) => Err(serde::de::Error::invalid_length(1, &self)),
+                    (None, _) => Err(serde::de::Error::invalid_length(0, &self)),
                 }
             }
         }
@@ -49,3 +63,435 @@ impl<'de> Deserialize<'de> for Vec2 {
         deserializer.deserialize_string(VecVisitor)
     }
 }
+
+/// A color, serialized as `rgba(RRGGBBAA)`
+///
+/// Deserialized from any way hyprlang writes colors: `rgba(RRGGBBAA)`, `rgb(RRGGBB)`, `rgba(r, g, b, a)`,
+/// `rgb(r, g, b)` or the integer `0xAARRGGBB`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Color {
+    /// From the integer hyprlang stores colors as, `0xAARRGGBB`
+    pub fn from_argb(argb: u32) -> Self {
+        let [a, r, g, b] = argb.to_be_bytes();
+        Color { r, g, b, a }
+    }
+
+    /// The integer hyprlang stores colors as, `0xAARRGGBB`
+    pub fn argb(&self) -> u32 {
+        u32::from_be_bytes([self.a, self.r, self.g, self.b])
+    }
+}
+
+impl std::fmt::Display for Color {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "rgba({:02x}{:02x}{:02x}{:02x})", self.r, self.g, self.b, self.a)
+    }
+}
+
+impl Serialize for Color {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de> {
+
+        struct ColorVisitor;
+
+        impl<'de> Visitor<'de> for ColorVisitor {
+            type Value = Color;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a color, such as `rgba(33ccffee)`")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+                where
+                    E: serde::de::Error, {
+                parse_color(v).map(Color::from_argb).map_err(|e| E::custom(format!("expected a color, found `{v}`: {e}")))
+            }
+
+            fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+                where
+                    E: serde::de::Error, {
+                u32::try_from(v).map(Color::from_argb).map_err(|_| E::invalid_value(serde::de::Unexpected::Signed(v), &self))
+            }
+
+            fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+                where
+                    E: serde::de::Error, {
+                u32::try_from(v).map(Color::from_argb).map_err(|_| E::invalid_value(serde::de::Unexpected::Unsigned(v), &self))
+            }
+        }
+
+        // Colors may be written as an integer, which only self-describing formats can tell apart
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(ColorVisitor)
+        } else {
+            deserializer.deserialize_string(ColorVisitor)
+        }
+    }
+}
+
+/// Colors blended at an angle, serialized as `rgba(33ccffee) rgba(00ff99ee) 45deg`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Gradient {
+    pub colors: Vec<Color>,
+    /// In degrees, only written if it is not 0
+    pub angle: f64,
+}
+
+impl std::fmt::Display for Gradient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let colors = self.colors.iter().map(Color::to_string).collect::<Vec<_>>();
+        f.write_str(&colors.join(" "))?;
+        if self.angle != 0.0 {
+            write!(f, " {}deg", self.angle)?;
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for Gradient {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Gradient {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de> {
+
+        struct GradientVisitor;
+
+        impl<'de> Visitor<'de> for GradientVisitor {
+            type Value = Gradient;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("colors followed by an optional angle, such as `rgba(33ccffee) rgba(00ff99ee) 45deg`")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+                where
+                    E: serde::de::Error, {
+                let (colors, angle) = parse_gradient(v).map_err(|e| E::custom(format!("expected a gradient, found `{v}`: {e}")))?;
+                Ok(Gradient { colors: colors.into_iter().map(Color::from_argb).collect(), angle })
+            }
+
+            // A single color, written as an integer
+            fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+                where
+                    E: serde::de::Error, {
+                Color::deserialize(v.into_deserializer()).map(|color| Gradient { colors: vec![color], angle: 0.0 })
+            }
+
+            fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+                where
+                    E: serde::de::Error, {
+                Color::deserialize(v.into_deserializer()).map(|color| Gradient { colors: vec![color], angle: 0.0 })
+            }
+        }
+
+        // Colors may be written as an integer, which only self-describing formats can tell apart
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(GradientVisitor)
+        } else {
+            deserializer.deserialize_string(GradientVisitor)
+        }
+    }
+}
+
+/// A value of any type, inferred from how it is written by the same rules as hyprlang
+///
+/// Bools are `true`/`yes`/`on` and their opposites, integers may be hex (`0x1f`), colors are written with
+/// `rgba(..)` or `rgb(..)`, a vec2 is two numbers separated by a space and a gradient is several such colors
+/// with an optional angle. Everything else is a string
+///
+/// ```
+/// use serde_hyprlang::{Color, Value, Vec2};
+///
+/// assert_eq!(Value::infer("0x10"), Value::Int(16));
+/// assert_eq!(Value::infer("1.5 -2"), Value::Vec2(Vec2(1.5, -2.0)));
+/// assert_eq!(Value::infer("rgb(33ccff)"), Value::Color(Color { r: 0x33, g: 0xcc, b: 0xff, a: 0xff }));
+/// ```
+///
+/// Use [`Inferred`] to keep the text, in case the guess was wrong
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Vec2(Vec2),
+    Color(Color),
+    Gradient(Gradient),
+    String(String),
+}
+
+impl Value {
+    /// The type hyprlang would read the text as
+    pub fn infer(text: &str) -> Self {
+        infer(text)
+    }
+}
+
+impl Serialize for Value {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer {
+        match self {
+            Value::Bool(bool) => serializer.serialize_bool(*bool),
+            Value::Int(int) => serializer.serialize_i64(*int),
+            Value::Float(float) => serializer.serialize_f64(*float),
+            Value::Vec2(vec) => vec.serialize(serializer),
+            Value::Color(color) => color.serialize(serializer),
+            Value::Gradient(gradient) => gradient.serialize(serializer),
+            Value::String(string) => serializer.serialize_str(string),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de> {
+        Inferred::deserialize(deserializer).map(|inferred| inferred.value)
+    }
+}
+
+/// Name of the newtype struct [`Inferred`] deserializes through, so our deserializer hands it the text
+pub(crate) const INFERRED_TOKEN: &str = "$serde_hyprlang::Inferred";
+
+/// A [`Value`] together with the text it was inferred from  
+/// It is serialized as that text, so it is written back unchanged
+///
+/// When the guess was wrong, such as a name that merely looks like a number, the text can be read again:
+///
+/// ```
+/// use serde_hyprlang::{Inferred, Value};
+///
+/// let workspace: Inferred = serde_hyprlang::from_str("0x1f").unwrap();
+/// assert_eq!(workspace.value, Value::Int(31));
+/// assert_eq!(workspace.text, "0x1f");
+/// ```
+///
+/// Under `#[serde(flatten)]` serde only keeps the value of bools and numbers,
+/// so their text is the value written out again (`0x1f` becomes `31`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Inferred {
+    pub value: Value,
+    pub text: String,
+}
+
+impl Inferred {
+    /// Infers the value of the text, see [`Value::infer`]
+    pub fn new(text: &str) -> Self {
+        Inferred { value: infer(text), text: text.to_string() }
+    }
+}
+
+impl Serialize for Inferred {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer {
+        serializer.serialize_str(&self.text)
+    }
+}
+
+impl<'de> Deserialize<'de> for Inferred {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de> {
+
+        struct InferredVisitor;
+
+        impl<'de> Visitor<'de> for InferredVisitor {
+            type Value = Inferred;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a value")
+            }
+
+            // Our deserializer hands us the text
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+                where
+                    E: serde::de::Error, {
+                Ok(Inferred::new(v))
+            }
+
+            fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E>
+                where
+                    E: serde::de::Error, {
+                Ok(Inferred { value: Value::Bool(v), text: v.to_string() })
+            }
+
+            fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+                where
+                    E: serde::de::Error, {
+                Ok(Inferred { value: Value::Int(v), text: v.to_string() })
+            }
+
+            fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+                where
+                    E: serde::de::Error, {
+                Ok(Inferred::new(&v.to_string()))
+            }
+
+            fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
+                where
+                    E: serde::de::Error, {
+                Ok(Inferred { value: Value::Float(v), text: v.to_string() })
+            }
+
+            fn visit_seq<A>(self, seq: A) -> Result<Self::Value, A::Error>
+                where
+                    A: serde::de::SeqAccess<'de>, {
+                let vec = Vec2::deserialize(serde::de::value::SeqAccessDeserializer::new(seq))?;
+                Ok(Inferred { text: format!("{} {}", vec.0, vec.1), value: Value::Vec2(vec) })
+            }
+
+            // Every other deserializer
+            fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+                where
+                    D: serde::Deserializer<'de>, {
+                deserializer.deserialize_any(self)
+            }
+        }
+
+        deserializer.deserialize_newtype_struct(INFERRED_TOKEN, InferredVisitor)
+    }
+}
+
+/// Name of the newtype struct [`VarRef`] deserializes through, so our deserializer knows to hand it the reference
+pub(crate) const VARREF_TOKEN: &str = "$serde_hyprlang::VarRef";
+
+/// A value that may instead be a reference to a hyprlang variable, such as `$mainMod`  
+/// It is serialized as `$name`, or as the value itself
+///
+/// When deserializing, a value that is nothing but a reference becomes [`VarRef::Variable`],
+/// unless the deserializer was told to [resolve them](crate::Deserializer::resolve_var_refs)
+#[derive(Debug, Clone, PartialEq)]
+pub enum VarRef<T> {
+    /// The name of the variable, without the `$`
+    Variable(String),
+    Value(T),
+}
+
+impl<T> VarRef<T> {
+    pub fn var(name: &str) -> Self {
+        VarRef::Variable(name.to_string())
+    }
+}
+
+impl<T> From<T> for VarRef<T> {
+    fn from(value: T) -> Self {
+        VarRef::Value(value)
+    }
+}
+
+impl<T: Serialize> Serialize for VarRef<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer {
+        match self {
+            VarRef::Variable(name) => serializer.serialize_newtype_struct(VARREF_TOKEN, format!("${name}").as_str()),
+            VarRef::Value(value) => value.serialize(serializer)
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for VarRef<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de> {
+
+        struct VarRefVisitor<T>(std::marker::PhantomData<T>);
+
+        impl<'de, T: Deserialize<'de>> Visitor<'de> for VarRefVisitor<T> {
+            type Value = VarRef<T>;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a value or a reference to a variable")
+            }
+
+            // Our deserializer hands us the name of the variable first (empty if it is none), then the value
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+                where
+                    A: serde::de::SeqAccess<'de>, {
+                match seq.next_element::<String>()? {
+                    Some(name) if !name.is_empty() => return Ok(VarRef::Variable(name)),
+                    Some(_) => (),
+                    None => return Err(serde::de::Error::invalid_length(0, &self))
+                }
+
+                match seq.next_element::<T>()? {
+                    Some(value) => Ok(VarRef::Value(value)),
+                    None => Err(serde::de::Error::invalid_length(1, &self))
+                }
+            }
+
+            // Every other deserializer
+            fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+                where
+                    D: serde::Deserializer<'de>, {
+                T::deserialize(deserializer).map(VarRef::Value)
+            }
+        }
+
+        deserializer.deserialize_newtype_struct(VARREF_TOKEN, VarRefVisitor(std::marker::PhantomData))
+    }
+}
+
+/// Name of the tuple struct [`Conditional`] serializes as, so our serializer knows to write the directives
+pub(crate) const CONDITIONAL_TOKEN: &str = "$serde_hyprlang::Conditional";
+
+/// A value that is wrapped in `# hyprlang if VAR` and `# hyprlang endif` when serialized
+///
+/// Only serialization is supported, the deserializer evaluates the conditions itself
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conditional<T> {
+    /// The name of the variable, without the `$`
+    pub variable: String,
+    /// Written as `# hyprlang if !VAR`
+    pub negated: bool,
+    pub value: T,
+}
+
+impl<T> Conditional<T> {
+    /// Only read if the variable holds
+    pub fn when(variable: &str, value: T) -> Self {
+        Conditional { variable: variable.to_string(), negated: false, value }
+    }
+
+    /// Only read if the variable does not hold
+    pub fn unless(variable: &str, value: T) -> Self {
+        Conditional { variable: variable.to_string(), negated: true, value }
+    }
+}
+
+impl<T: Serialize> Serialize for Conditional<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer {
+        let condition = if self.negated { format!("!{}", self.variable) } else { self.variable.clone() };
+
+        let mut tupple = serializer.serialize_tuple_struct(CONDITIONAL_TOKEN, 2)?;
+        tupple.serialize_field(&condition)?;
+        tupple.serialize_field(&self.value)?;
+        tupple.end()
+    }
+}
@@ -0,0 +1,158 @@
+//! hyprlang variables, `$name = value`, and their expansion within values
+//!
+//! Expansion also evaluates the arithmetic between `{{` and `}}`, see [`expr`](crate::expr)
+
+use std::collections::BTreeMap;
+
+use crate::env::Environment;
+use crate::escape::unescape_hashes;
+use crate::expr::evaluate;
+use crate::parse::Position;
+use crate::{Error, Result, SyntaxKind};
+
+/// Every variable defined so far, with its value as written
+#[derive(Clone)]
+pub(crate) struct Variables<'e> {
+    definitions: BTreeMap<String, Definition>,
+    /// Consulted for `$name`s the text does not define
+    environment: Option<&'e dyn Environment>,
+    /// Names found in neither are kept as they are written, instead of failing
+    keep_unresolved: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Definition {
+    value: String,
+    /// Where the value starts
+    position: Position,
+}
+
+impl<'e> Variables<'e> {
+    pub(crate) fn new(environment: Option<&'e dyn Environment>, keep_unresolved: bool) -> Self {
+        Variables { definitions: BTreeMap::new(), environment, keep_unresolved }
+    }
+
+    pub(crate) fn define(&mut self, name: &str, value: &str, position: Position) {
+        self.definitions.insert(name.to_string(), Definition { value: value.to_string(), position });
+    }
+
+    /// Replaces every `$name` within the text with the value of the variable,
+    /// and every `{{ expression }}` with its result.
+    /// Variables may refer to other variables, these are expanded too.
+    /// A `\` right before a `$` or `{{` is dropped and keeps them from being expanded
+    ///
+    /// `position` is where the text starts, and `path` the key it is assigned to, both are used for errors
+    pub(crate) fn expand(&self, text: &str, position: &Position, path: &str) -> Result<String> {
+        self.expand_inner(text, position, path, &mut vec![])
+    }
+
+    /// The expanded value of the variable (or the environment variable), `None` if it is not defined
+    pub(crate) fn value(&self, name: &str, position: &Position, len: usize, path: &str) -> Result<Option<String>> {
+        self.lookup(name, position, len, path, &mut vec![]).map(|value| value.map(|value| unescape_hashes(&value)))
+    }
+
+    /// Expands the value of every variable
+    pub(crate) fn resolve_all(&self) -> Result<BTreeMap<String, String>> {
+        self.definitions.iter()
+            .map(|(name, definition)| {
+                let value = self.resolve(name, &definition.position, name.len(), "", &mut vec![])?;
+                Ok((name.clone(), unescape_hashes(&value)))
+            })
+            .collect()
+    }
+
+    /// The value of the variable, falling back to the environment, `None` if it is in neither
+    ///
+    /// Values from the environment have their `#` escaped, like the text around them
+    fn lookup(&self, name: &str, position: &Position, len: usize, path: &str, stack: &mut Vec<String>) -> Result<Option<String>> {
+        if self.definitions.contains_key(name) {
+            return self.resolve(name, position, len, path, stack).map(Some);
+        }
+
+        Ok(self.environment.and_then(|environment| environment.var(name)).map(|value| value.replace('#', "##")))
+    }
+
+    /// `position` and `len` are of the `$name` referring to the variable
+    fn resolve(&self, name: &str, position: &Position, len: usize, path: &str, stack: &mut Vec<String>) -> Result<String> {
+        let Some(definition) = self.definitions.get(name) else {
+            return match self.lookup(name, position, len, path, stack)? {
+                Some(value) => Ok(value),
+                None if self.keep_unresolved => Ok(format!("${name}")),
+                None if self.environment.is_some() => {
+                    let msg = format!("variable `${name}` is not defined, and not set in the environment");
+                    Err(Error::Syntax(SyntaxKind::UndefinedVariable, msg).at(|| position.location(len, path)))
+                },
+                None => Err(Error::Syntax(SyntaxKind::UndefinedVariable, format!("variable `${name}` is not defined")).at(|| position.location(len, path)))
+            };
+        };
+
+        if stack.iter().any(|n| n == name) {
+            let cycle = stack.iter().map(|n| format!("${n} -> ")).collect::<String>();
+            let msg = format!("variable `${name}` refers to itself: {cycle}${name}");
+            return Err(Error::Syntax(SyntaxKind::RecursiveVariable, msg).at(|| position.location(len, path)));
+        }
+
+        stack.push(name.to_string());
+        let value = self.expand_inner(&definition.value, &definition.position, &format!("${name}"), stack);
+        stack.pop();
+
+        value
+    }
+
+    fn expand_inner(&self, text: &str, position: &Position, path: &str, stack: &mut Vec<String>) -> Result<String> {
+        let mut output = String::with_capacity(text.len());
+        let mut rest = text;
+
+        while let Some(index) = rest.find(['$', '{', '\\']) {
+            output += &rest[..index];
+            let start = text.len() - rest.len() + index;
+
+            if let Some(after) = rest[index..].strip_prefix('\\') {
+                // `\$` and `\{{` are written as they are, any other `\` is kept
+                let len = if after.starts_with('$') { 1 } else if after.starts_with("{{") { 2 } else { 0 };
+                output += if len == 0 { "\\" } else { &after[..len] };
+                rest = &after[len..];
+                continue;
+            }
+
+            if rest[index..].starts_with("{{") {
+                let inner = &rest[index + 2..];
+                let Some(len) = inner.find("}}") else {
+                    let msg = "expression `{{` is never closed";
+                    return Err(Error::Syntax(SyntaxKind::UnclosedExpression, msg.to_string()).at(|| position.advance(text, start).location(2, path)));
+                };
+
+                let mut lookup = |name: &str, reference: &Position, len: usize| self.lookup(name, reference, len, path, stack);
+                output += &evaluate(&inner[..len], &position.advance(text, start + 2), path, &mut lookup)?.to_string();
+
+                rest = &inner[len + 2..];
+                continue;
+            }
+
+            let after = &rest[index + 1..];
+            if rest[index..].starts_with('{') {
+                output += "{";
+                rest = after;
+                continue;
+            }
+
+            let name_len = after.find(|c: char| !is_name_char(c)).unwrap_or(after.len());
+            if name_len == 0 {
+                // A lone `$`, such as in the regex `^(kitty)$`
+                output += "$";
+            } else {
+                let reference = position.advance(text, start);
+                output += &self.resolve(&after[..name_len], &reference, name_len + 1, path, stack)?;
+            }
+
+            rest = &after[name_len..];
+        }
+        output += rest;
+
+        Ok(output)
+    }
+}
+
+pub(crate) fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
@@ -0,0 +1,175 @@
+use serde::{Deserialize, Serialize};
+use serde_hyprlang::Conditional;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct General {
+    gaps_in: u8,
+    border_size: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Config {
+    monitor: String,
+    general: General,
+}
+
+#[test]
+fn conditional_deserialize() {
+    let t =
+"$BORDERS = 0
+monitor = eDP-1, preferred, auto, 1
+# hyprlang if !LAPTOP
+monitor = DP-1, 2560x1440, auto, 1
+# hyprlang endif
+
+general {
+    gaps_in = 5
+    border_size = 1
+    # hyprlang if LAPTOP
+        gaps_in = 2
+        # hyprlang if BORDERS
+        border_size = 3
+        # hyprlang endif
+    # hyprlang endif
+}
+";
+    let mut de = serde_hyprlang::Deserializer::from_str(t).variable("LAPTOP", "true");
+    let res = Config::deserialize(&mut de);
+
+    let expect = Config {
+        monitor: "eDP-1, preferred, auto, 1".to_string(),
+        general: General { gaps_in: 2, border_size: 1 }
+    };
+
+    assert_eq!(res, Ok(expect), "Failed to evaluate conditions");
+
+    let res = serde_hyprlang::from_str(t);
+
+    let expect = Config {
+        monitor: "DP-1, 2560x1440, auto, 1".to_string(),
+        general: General { gaps_in: 5, border_size: 1 }
+    };
+
+    assert_eq!(res, Ok(expect), "Failed to evaluate conditions of undefined variables");
+}
+
+#[test]
+fn conditional_errors() {
+    let t = "monitor = eDP-1\n  # hyprlang endif\n";
+    let res = serde_hyprlang::from_str::<Config>(t).map_err(|e| e.to_string());
+    assert_eq!(res, Err("2:3: `# hyprlang endif` without a `# hyprlang if`".to_string()), "Failed to report an endif without an if");
+
+    let t = "# hyprlang if LAPTOP\nmonitor = eDP-1\n";
+    let res = serde_hyprlang::from_str::<Config>(t).map_err(|e| e.to_string());
+    assert_eq!(res, Err("1:1: `# hyprlang if` without a `# hyprlang endif`".to_string()), "Failed to report an if without an endif");
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct SerGeneral {
+    gaps_in: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct ConditionalConfig {
+    monitor: Vec<Conditional<String>>,
+    general: Conditional<SerGeneral>,
+}
+
+#[test]
+fn conditional_serialize() {
+    let c = ConditionalConfig {
+        monitor: vec![
+            Conditional::when("LAPTOP", "eDP-1, preferred, auto, 1".to_string()),
+            Conditional::unless("LAPTOP", "DP-1, 2560x1440, auto, 1".to_string()),
+        ],
+        general: Conditional::when("LAPTOP", SerGeneral { gaps_in: 2 })
+    };
+
+    let res = serde_hyprlang::to_string(&c);
+
+    let expect =
+"# hyprlang if LAPTOP
+monitor = \"eDP-1, preferred, auto, 1\"
+# hyprlang endif
+# hyprlang if !LAPTOP
+monitor = \"DP-1, 2560x1440, auto, 1\"
+# hyprlang endif
+
+# hyprlang if LAPTOP
+general {
+    gaps_in = 2
+}
+# hyprlang endif
+".to_string();
+
+    assert_eq!(res, Ok(expect), "Failed to write conditional blocks");
+}
+
+#[test]
+fn conditional_serialize_errors() {
+    let c = ConditionalConfig {
+        monitor: vec![Conditional::when("LAP TOP", "eDP-1, preferred, auto, 1".to_string())],
+        general: Conditional::when("LAPTOP", SerGeneral { gaps_in: 2 })
+    };
+    let res = serde_hyprlang::to_string(&c).map_err(|e| e.to_string());
+    assert_eq!(res, Err("`LAP TOP` can not be written as a key".to_string()), "Failed to reject a condition with a space");
+
+    let c = ConditionalConfig {
+        monitor: vec![],
+        general: Conditional::unless("", SerGeneral { gaps_in: 2 })
+    };
+    let res = serde_hyprlang::to_string(&c).map_err(|e| e.to_string());
+    assert_eq!(res, Err("`!` can not be written as a key".to_string()), "Failed to reject an empty condition");
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Strict {
+    gaps_in: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StrictConfig {
+    general: Strict,
+}
+
+#[test]
+fn noerror_deserialize() {
+    let t =
+"general {
+    gaps_in = 5
+    # hyprlang noerror true
+    only_in_newer_versions = 1
+    this line is broken
+    gaps_in = $undefined
+    # hyprlang noerror false
+}
+";
+    let mut de = serde_hyprlang::Deserializer::from_str(t);
+    let res = StrictConfig::deserialize(&mut de);
+
+    assert_eq!(res, Ok(StrictConfig { general: Strict { gaps_in: 5 } }), "Failed to ignore errors within a noerror region");
+
+    let warnings = de.warnings().iter().map(|w| w.to_string()).collect::<Vec<_>>();
+    let expect = vec![
+        "5:5: expected `key = value`, `category {` or `}`, found `this line is broken` (in `general`)".to_string(),
+        "6:15: variable `$undefined` is not defined (in `general:gaps_in`)".to_string(),
+        "4:5: unknown key `only_in_newer_versions` (in `general:only_in_newer_versions`)".to_string(),
+    ];
+
+    assert_eq!(warnings, expect, "Failed to collect the warnings");
+
+    let t = "general {\n    # hyprlang noerror true\n    # hyprlang noerror false\n    unknown = 1\n    gaps_in = 5\n}\n";
+    let res = serde_hyprlang::from_str::<StrictConfig>(t).map_err(|e| e.to_string());
+
+    assert_eq!(res, Err("4:5: unknown field `unknown`, expected `gaps_in` (in `general:unknown`)".to_string()), "Failed to end the noerror region");
+
+    let t = "general {\n    gaps_in = 5\n    # hyprlang noerror true\n    gaps_in = abc\n}\n";
+    let mut de = serde_hyprlang::Deserializer::from_str(t);
+    let res = StrictConfig::deserialize(&mut de);
+
+    assert_eq!(res, Ok(StrictConfig { general: Strict { gaps_in: 5 } }), "Failed to keep the earlier value of a type error within a noerror region");
+    let warnings = de.warnings().iter().map(|w| w.to_string()).collect::<Vec<_>>();
+    assert_eq!(warnings, vec!["4:15: expected an unsigned integer, found `abc`: invalid digit found in string (in `general:gaps_in`)".to_string()], "Failed to warn about a type error within a noerror region");
+}
@@ -0,0 +1,144 @@
+use serde::Deserialize;
+use serde_hyprlang::Diagnostic;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Blur {
+    size: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Decoration {
+    blur: Blur
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Config {
+    decoration: Decoration,
+}
+
+#[test]
+fn plain_diagnostic() {
+    let t =
+"decoration {
+    blur {
+        size = big
+    }
+}
+";
+    let mut de = serde_hyprlang::Deserializer::from_str(t).file_name("hyprland.conf");
+    let err = Config::deserialize(&mut de).unwrap_err();
+    let res = Diagnostic::new(&err).file("hyprland.conf", t).to_string();
+
+    let expect =
+"error: expected an unsigned integer, found `big`: invalid digit found in string
+ --> hyprland.conf:3:16
+  |
+3 |         size = big
+  |                ^^^ in `decoration:blur:size`
+".to_string();
+
+    assert_eq!(res, expect, "Failed to render a diagnostic");
+}
+
+#[test]
+fn hint_diagnostic() {
+    let t = "decoration {\n\tblur:size = $size\n}\n";
+    let err = serde_hyprlang::from_str::<Config>(t).unwrap_err();
+    let res = Diagnostic::new(&err).source(t).to_string();
+
+    let expect =
+"error: variable `$size` is not defined
+ --> 2:14
+  |
+2 | \tblur:size = $size
+  | \t            ^^^^^ in `decoration:blur:size`
+  = hint: variables have to be defined with `$name = value` before they are used
+".to_string();
+
+    assert_eq!(res, expect, "Failed to render a diagnostic with a hint");
+
+    let res = Diagnostic::new(&err).source(t).colored(true).to_string();
+    assert!(res.starts_with("\x1b[1;31merror\x1b[0m"), "Failed to color a diagnostic");
+}
+
+#[test]
+fn unknown_file_diagnostic() {
+    let fs = serde_hyprlang::VirtualFileSystem::new().file("/c/binds.conf", "decoration:blur:size = big\n");
+    let t = "# header\nsource = /c/binds.conf\n";
+    let mut de = serde_hyprlang::Deserializer::from_str(t).resolver(fs);
+    let err = Config::deserialize(&mut de).unwrap_err();
+    let res = Diagnostic::new(&err).source(t).to_string();
+
+    let expect =
+"error: expected an unsigned integer, found `big`: invalid digit found in string
+ --> /c/binds.conf:1:24
+".to_string();
+
+    assert_eq!(res, expect, "Failed to leave out the snippet of a file that was not handed in");
+}
+
+#[test]
+fn unlocated_diagnostic() {
+    let err = serde_hyprlang::to_string(&std::collections::HashMap::from([("a b", 1)])).unwrap_err();
+    let res = Diagnostic::new(&err).to_string();
+
+    let expect =
+"error: `a b` can not be written as a key
+ = hint: keys can not be empty, start with `$` or contain whitespace or any of `= { } # , : [ ] \"`
+".to_string();
+
+    assert_eq!(res, expect, "Failed to render a diagnostic without a location");
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Single {
+    value: String,
+}
+
+#[test]
+fn hints() {
+    let hint = |t: &str| {
+        let err = serde_hyprlang::from_str::<Single>(t).unwrap_err();
+        Diagnostic::new(&err).source(t).to_string().lines().find_map(|line| line.split_once("hint: ").map(|(_, hint)| hint.to_string()))
+    };
+
+    assert_eq!(hint("value = $nope\n").as_deref(), Some("variables have to be defined with `$name = value` before they are used"), "Failed to hint at an undefined variable");
+    assert_eq!(hint("$a = x$a\nvalue = $a\n").as_deref(), Some("a variable can not be part of its own value"), "Failed to hint at a variable referring to itself");
+    assert_eq!(hint("value = {{ 1 + 1\n").as_deref(), Some("expressions are closed with `}}` on the same line"), "Failed to hint at an unclosed expression");
+    assert_eq!(hint("value = {{ (1 + 1 }}\n").as_deref(), Some("every `(` within an expression needs a `)`"), "Failed to hint at an unclosed parenthesis");
+    assert_eq!(hint("value = 1\ngeneral {\n").as_deref(), Some("every `category {` needs a `}` on a line of its own"), "Failed to hint at an unclosed category");
+    assert_eq!(hint("value = 1\n}\n").as_deref(), Some("there is one `}` too many, or a `category {` is missing"), "Failed to hint at a closing bracket too many");
+    assert_eq!(hint("other = 1\n").as_deref(), Some("add the key to this category"), "Failed to hint at a missing field");
+    assert_eq!(hint("value is not defined\n"), None, "Failed to pick the hint by the kind of error instead of its text");
+}
+
+#[cfg(feature = "miette")]
+#[test]
+fn miette_diagnostic() {
+    use miette::Diagnostic as _;
+
+    let t = "decoration {\n    blur:size = $size\n}\n";
+    let err = serde_hyprlang::from_str::<Config>(t).unwrap_err();
+
+    let labels = err.labels().map(|labels| labels.map(|l| (l.offset(), l.len(), l.label().map(str::to_string))).collect::<Vec<_>>());
+    assert_eq!(labels, Some(vec![(29, 5, Some("in `decoration:blur:size`".to_string()))]), "Failed to label the span");
+    assert!(err.help().is_some(), "Failed to provide the hint as help");
+}
+
+#[cfg(feature = "miette")]
+#[test]
+fn miette_include_diagnostic() {
+    use miette::Diagnostic as _;
+
+    let fs = serde_hyprlang::VirtualFileSystem::new().file("/c/binds.conf", "decoration:blur:size = big\n");
+    let t = "# header\nsource = /c/binds.conf\n";
+    let mut de = serde_hyprlang::Deserializer::from_str(t).file_name("/c/hyprland.conf").resolver(fs);
+    let err = Config::deserialize(&mut de).unwrap_err();
+    assert!(err.labels().is_none(), "Failed to leave out the label of an error in an included file");
+
+    let t = "decoration:blur:size = big\n";
+    let mut de = serde_hyprlang::Deserializer::from_str(t).file_name("/c/hyprland.conf");
+    let err = Config::deserialize(&mut de).unwrap_err();
+    let labels = err.labels().map(|labels| labels.map(|l| (l.offset(), l.len())).collect::<Vec<_>>());
+    assert_eq!(labels, Some(vec![(23, 3)]), "Failed to label an error in the named text");
+}
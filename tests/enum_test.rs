@@ -0,0 +1,366 @@
+use serde::Deserialize;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Dwindle {
+    pseudotile: bool,
+    split_width_multiplier: f32
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+enum Layout {
+    Master,
+    Dwindle(Dwindle),
+    Scrolling { column_width: f32 },
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+enum Dispatcher {
+    KillActive,
+    Exec(String),
+    MoveFocus(char),
+    ResizeActive(i32, i32),
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct General {
+    layout: Layout,
+    fallback: Layout,
+    bind: Vec<(String, String, Dispatcher)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Root {
+    general: General,
+    alternative: Layout,
+}
+
+#[test]
+fn enum_deserialize() {
+    let t =
+"general {
+    layout {
+        Dwindle {
+            pseudotile = yes
+            split_width_multiplier = 1.5
+        }
+    }
+    fallback = Master
+    bind = SUPER, Q, KillActive
+    bind = SUPER, Return, Exec, kitty, --single-instance
+    bind = SUPER, left, MoveFocus, l
+    bind = SUPER ALT, right, ResizeActive, 10, -10
+}
+alternative:Scrolling:column_width = 0.5
+";
+    let res = serde_hyprlang::from_str(t);
+
+    let expect = Root {
+        general: General {
+            layout: Layout::Dwindle(Dwindle { pseudotile: true, split_width_multiplier: 1.5 }),
+            fallback: Layout::Master,
+            bind: vec![
+                ("SUPER".to_string(), "Q".to_string(), Dispatcher::KillActive),
+                ("SUPER".to_string(), "Return".to_string(), Dispatcher::Exec("kitty, --single-instance".to_string())),
+                ("SUPER".to_string(), "left".to_string(), Dispatcher::MoveFocus('l')),
+                ("SUPER ALT".to_string(), "right".to_string(), Dispatcher::ResizeActive(10, -10)),
+            ]
+        },
+        alternative: Layout::Scrolling { column_width: 0.5 }
+    };
+
+    assert_eq!(res, Ok(expect), "Failed to decode enums with data");
+
+    assert_eq!(serde_hyprlang::from_str("ResizeActive, 5, 5"), Ok(Dispatcher::ResizeActive(5, 5)), "Failed to decode an enum from a value");
+    assert_eq!(serde_hyprlang::from_str("Exec = firefox\n"), Ok(Dispatcher::Exec("firefox".to_string())), "Failed to decode an enum keyed by its variant");
+    assert_eq!(serde_hyprlang::from_str("KillActive"), Ok(Dispatcher::KillActive), "Failed to decode a unit variant");
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Binds {
+    bind: (String, Dispatcher),
+    layout: Layout,
+}
+
+#[test]
+fn enum_errors() {
+    let error = |t: &str| serde_hyprlang::from_str::<Binds>(t).map_err(|e| e.to_string());
+
+    assert_eq!(error("bind = Q, Sleep\nlayout = Master\n"), Err("1:11: unknown variant `Sleep`, expected one of `KillActive`, `Exec`, `MoveFocus`, `ResizeActive` (in `bind`)".to_string()), "Failed to report an unknown variant");
+    assert_eq!(error("bind = Q, ResizeActive, 10\nlayout = Master\n"), Err("1:8: not enough elements in `Q, ResizeActive, 10` (in `bind`)".to_string()), "Failed to report missing data");
+    assert_eq!(error("bind = Q, KillActive, now\nlayout = Master\n"), Err("1:8: too many elements in `Q, KillActive, now` (in `bind`)".to_string()), "Failed to report data of a unit variant");
+    assert_eq!(error("bind = Q, KillActive\nlayout {\n    Master =\n    Dwindle {\n    }\n}\n"), Err("4:5: expected a single variant of `Layout`, found `Master` and `Dwindle` (in `layout`)".to_string()), "Failed to report two variants");
+    assert_eq!(error("bind = Q, KillActive\nlayout:Master = 1\n"), Err("2:17: the variant `Master` has no data (in `layout:Master`)".to_string()), "Failed to report data of a unit variant in a category");
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Keyword {
+    Bind(String, String, String, String),
+    Bindm(String, String, String),
+    Unbind(String, String),
+    Submap(String),
+    #[serde(rename = "exec-once")]
+    ExecOnce(String),
+    Reset,
+    General { gaps_in: u8 },
+}
+
+#[test]
+fn keyword_variants_roundtrip() {
+    let t =
+"exec-once = waybar
+bind = SUPER, R, submap, resize
+submap = resize
+bind = , escape, submap, reset
+submap = reset
+bindm = SUPER, mouse:272, movewindow
+unbind = SUPER, Q
+reset =
+general {
+    gaps_in = 5
+}
+";
+    let res = Vec::<Keyword>::deserialize(&mut serde_hyprlang::Deserializer::from_str(t).keyword_variants());
+
+    let expect = vec![
+        Keyword::ExecOnce("waybar".to_string()),
+        Keyword::Bind("SUPER".to_string(), "R".to_string(), "submap".to_string(), "resize".to_string()),
+        Keyword::Submap("resize".to_string()),
+        Keyword::Bind("".to_string(), "escape".to_string(), "submap".to_string(), "reset".to_string()),
+        Keyword::Submap("reset".to_string()),
+        Keyword::Bindm("SUPER".to_string(), "mouse:272".to_string(), "movewindow".to_string()),
+        Keyword::Unbind("SUPER".to_string(), "Q".to_string()),
+        Keyword::Reset,
+        Keyword::General { gaps_in: 5 },
+    ];
+
+    assert_eq!(res, Ok(expect.clone()), "Failed to decode the variants from the keywords");
+
+    let res = serde_hyprlang::to_string_with(&expect, serde_hyprlang::Serializer::new().keyword_variants());
+
+    let text =
+"exec-once = waybar
+bind = SUPER, R, submap, resize
+submap = resize
+bind = , escape, submap, reset
+submap = reset
+bindm = SUPER, mouse:272, movewindow
+unbind = SUPER, Q
+reset = 
+general {
+    gaps_in = 5
+}
+
+".to_string();
+
+    assert_eq!(res, Ok(text.clone()), "Failed to encode the variants as keywords");
+
+    let res = Vec::<Keyword>::deserialize(&mut serde_hyprlang::Deserializer::from_str(&text).keyword_variants());
+    assert_eq!(res, Ok(expect), "Failed to roundtrip keyword variants");
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, Deserialize)]
+struct KeywordField {
+    lines: Vec<Keyword>
+}
+
+#[test]
+fn keyword_variants_field_roundtrip() {
+    let t = KeywordField {
+        lines: vec![Keyword::Submap("resize".to_string()), Keyword::ExecOnce("waybar".to_string())]
+    };
+    let ser = serde_hyprlang::Serializer::new().keyword_variants().enum_tagging(serde_hyprlang::EnumTagging::FirstElement);
+    let res = serde_hyprlang::to_string_with(&t, ser);
+
+    let text = "lines = submap, resize\nlines = exec-once, waybar\n\n".to_string();
+    assert_eq!(res, Ok(text.clone()), "Failed to keep the name of a sequence within a struct");
+
+    let res = KeywordField::deserialize(&mut serde_hyprlang::Deserializer::from_str(&text).keyword_variants());
+    assert_eq!(res, Ok(t), "Failed to roundtrip a sequence within a struct");
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Action {
+    KillActive,
+    Exec(String),
+    MoveFocus(char),
+    ResizeActive(i32, i32),
+    Focus { monitor: u8 },
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, Deserialize)]
+enum Shape {
+    Master,
+    Gaps(u8),
+    Offset(i32, i32),
+    Scrolling { column_width: f32 },
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, Deserialize)]
+struct Tagged {
+    bind: Vec<(String, String, Action)>,
+    shapes: Vec<Shape>,
+}
+
+#[test]
+fn enum_tagging_roundtrip() {
+    let tagged = Tagged {
+        bind: vec![
+            ("SUPER".to_string(), "Q".to_string(), Action::KillActive),
+            ("SUPER".to_string(), "Return".to_string(), Action::Exec("kitty".to_string())),
+            ("SUPER".to_string(), "left".to_string(), Action::MoveFocus('l')),
+            ("SUPER ALT".to_string(), "right".to_string(), Action::ResizeActive(10, -10)),
+            ("SUPER".to_string(), "1".to_string(), Action::Focus { monitor: 1 }),
+        ],
+        shapes: vec![Shape::Master, Shape::Gaps(5), Shape::Offset(2, -2), Shape::Scrolling { column_width: 0.5 }],
+    };
+
+    let res = serde_hyprlang::to_string_with(&tagged, serde_hyprlang::Serializer::new().enum_tagging(serde_hyprlang::EnumTagging::FirstElement));
+
+    let text =
+"bind = SUPER, Q, killactive
+bind = SUPER, Return, exec, kitty
+bind = SUPER, left, movefocus, l
+bind = SUPER ALT, right, resizeactive, 10, -10
+bind = SUPER, 1, focus, monitor: 1
+
+shapes = Master
+shapes = Gaps, 5
+shapes = Offset, 2, -2
+shapes = Scrolling, column_width: 0.5
+
+".to_string();
+
+    assert_eq!(res, Ok(text.clone()), "Failed to encode the variants as first elements");
+    assert_eq!(serde_hyprlang::from_str(&text), Ok(tagged.clone()), "Failed to roundtrip variants as first elements");
+
+    let key = || serde_hyprlang::Serializer::new().enum_tagging(serde_hyprlang::EnumTagging::Key);
+    assert_eq!(serde_hyprlang::to_string_with(&Shape::Offset(2, -2), key()), Ok("Offset = 2, -2\n".to_string()), "Failed to encode a tuple variant as a key");
+
+    let res = serde_hyprlang::to_string_with(&Shape::Scrolling { column_width: 0.5 }, key());
+    let text =
+"Scrolling {
+    column_width = 0.5
+}
+
+".to_string();
+
+    assert_eq!(res, Ok(text), "Failed to encode the variants as keys");
+
+    let res = serde_hyprlang::to_string_with(&tagged, key()).map(|text| serde_hyprlang::from_str(&text));
+    assert_eq!(res, Ok(Ok(tagged)), "Failed to roundtrip variants as keys");
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Auto {
+    Auto,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, Deserialize)]
+#[serde(untagged)]
+enum Size {
+    Auto(Auto),
+    Pixels(u32),
+    Scaled(f64),
+    Split { ratio: f64, vertical: bool },
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Device {
+    Mouse { sensitivity: f64, natural_scroll: bool },
+    Keyboard { kb_layout: String },
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, Deserialize)]
+struct Dynamic {
+    width: Size,
+    height: Size,
+    scale: Size,
+    split: Size,
+    monitor: (String, Size),
+    device: Vec<Device>,
+    primary: Device,
+}
+
+#[test]
+fn tagged_untagged_roundtrip() {
+    let t =
+"width = auto
+height = 1080
+scale = 1.5
+split {
+    ratio = 0.5
+    vertical = yes
+}
+monitor = DP-1, auto
+device {
+    type = mouse
+    sensitivity = -0.25
+    natural_scroll = true
+}
+device {
+    type = keyboard
+    kb_layout = us
+}
+primary:type = mouse
+primary:sensitivity = 0
+primary:natural_scroll = off
+";
+    let res = serde_hyprlang::from_str(t);
+
+    let expect = Dynamic {
+        width: Size::Auto(Auto::Auto),
+        height: Size::Pixels(1080),
+        scale: Size::Scaled(1.5),
+        split: Size::Split { ratio: 0.5, vertical: true },
+        monitor: ("DP-1".to_string(), Size::Auto(Auto::Auto)),
+        device: vec![
+            Device::Mouse { sensitivity: -0.25, natural_scroll: true },
+            Device::Keyboard { kb_layout: "us".to_string() },
+        ],
+        primary: Device::Mouse { sensitivity: 0.0, natural_scroll: false },
+    };
+
+    assert_eq!(res, Ok(expect.clone()), "Failed to decode internally tagged and untagged enums");
+
+    let res = serde_hyprlang::to_string(&expect);
+
+    let text =
+"width = auto
+height = 1080
+scale = 1.5
+split {
+    ratio = 0.5
+    vertical = true
+}
+
+monitor = DP-1, auto
+device {
+    type = mouse
+    sensitivity = -0.25
+    natural_scroll = true
+}
+
+device {
+    type = keyboard
+    kb_layout = us
+}
+
+
+primary {
+    type = mouse
+    sensitivity = 0
+    natural_scroll = false
+}
+
+".to_string();
+
+    assert_eq!(res, Ok(text.clone()), "Failed to encode internally tagged and untagged enums");
+    assert_eq!(serde_hyprlang::from_str(&text), Ok(expect), "Failed to roundtrip internally tagged and untagged enums");
+
+    assert_eq!(serde_hyprlang::from_str("auto"), Ok(Size::Auto(Auto::Auto)), "Failed to decode an untagged enum from a value");
+    assert_eq!(serde_hyprlang::from_str("0x20"), Ok(Size::Pixels(32)), "Failed to decode a hex integer as any");
+}
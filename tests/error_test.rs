@@ -0,0 +1,73 @@
+use serde::Deserialize;
+use serde_hyprlang::Location;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Blur {
+    size: u8,
+    passes: u8
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Decoration {
+    rounding: u8,
+    blur: Blur
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Config {
+    decoration: Decoration,
+    exec: Vec<(usize, bool)>
+}
+
+#[test]
+fn value_error_location() {
+    let t =
+"exec = 1, true
+decoration {
+    rounding = 8
+    blur {
+        size = big
+        passes = 2
+    }
+}
+";
+    let mut de = serde_hyprlang::Deserializer::from_str(t).file_name("hyprland.conf");
+    let res = Config::deserialize(&mut de).map_err(|e| e.to_string());
+
+    assert_eq!(res, Err("hyprland.conf:5:16: expected an unsigned integer, found `big`: invalid digit found in string (in `decoration:blur:size`)".to_string()), "Failed to locate a value");
+
+    let t = "decoration:rounding = 8\ndecoration:blur:size = 8\ndecoration:blur:passes = 2\nexec = 1, true\nexec = 2,   maybe\n";
+    let res = serde_hyprlang::from_str::<Config>(t);
+    let location = res.as_ref().err().and_then(|e| e.location()).cloned();
+
+    let expect = Location {
+        file: None,
+        line: 5,
+        column: 13,
+        span: 103..108,
+        path: "exec".to_string(),
+        included: false
+    };
+
+    assert_eq!(location, Some(expect), "Failed to locate a tupple element");
+}
+
+#[test]
+fn missing_field_location() {
+    let t = "exec = 1, true\ndecoration {\n    rounding = 8\n}\n";
+    let res = serde_hyprlang::from_str::<Config>(t).map_err(|e| e.to_string());
+
+    assert_eq!(res, Err("2:1: missing field `blur` (in `decoration`)".to_string()), "Failed to locate a missing field");
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Triple {
+    t: (i32, i32, i32)
+}
+
+#[test]
+fn too_many_elements() {
+    let res = serde_hyprlang::from_str::<Triple>("t = 1, 2, 3, 4\n").map_err(|e| e.to_string());
+
+    assert_eq!(res, Err("1:5: too many elements in `1, 2, 3, 4` (in `t`)".to_string()), "Failed to count the elements of a tupple of numbers");
+}
@@ -0,0 +1,88 @@
+use serde::Deserialize;
+use serde_hyprlang::Number;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Gaps {
+    gaps_in: i32,
+    gaps_out: i32,
+    rounding: f32,
+    border: String
+}
+
+#[test]
+fn expression_deserialize() {
+    let t =
+"$gap = 5
+$double = {{ $gap * 2 }}
+gaps_in = {{gap}}
+gaps_out = {{ $double + 7 / 2 - (1 - 3) }}
+rounding = {{ $gap / 2.0 }}
+border = {{ -$gap }}px, { literal }
+";
+    let res = serde_hyprlang::from_str(t);
+
+    let expect = Gaps {
+        gaps_in: 5,
+        gaps_out: 15,
+        rounding: 2.5,
+        border: "-5px, { literal }".to_string()
+    };
+
+    assert_eq!(res, Ok(expect), "Failed to evaluate expressions");
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Single {
+    value: String
+}
+
+#[test]
+fn expression_errors() {
+    let error = |t: &str| serde_hyprlang::from_str::<Single>(t).map_err(|e| e.to_string());
+
+    assert_eq!(error("value = {{ 4 / (2 - 2) }}\n"), Err("1:12: division by zero (in `value`)".to_string()), "Failed to report a division by zero");
+    assert_eq!(error("value = {{ 1 + $nope }}\n"), Err("1:16: variable `$nope` is not defined (in `value`)".to_string()), "Failed to report an undefined variable");
+    assert_eq!(error("$a = kitty\nvalue = {{ a * 2 }}\n"), Err("2:12: variable `$a` is `kitty`, which is not a number (in `value`)".to_string()), "Failed to report a variable that is not a number");
+    assert_eq!(error("value = {{ 1 + }}\n"), Err("1:16: expected a number, found the end of the expression (in `value`)".to_string()), "Failed to report a missing operand");
+    assert_eq!(error("value = {{ (1 + 2 }}\n"), Err("1:12: `(` is never closed (in `value`)".to_string()), "Failed to report an unclosed parenthesis");
+    assert_eq!(error("value = {{ 2 2 }}\n"), Err("1:14: expected an operator, found `2` (in `value`)".to_string()), "Failed to report a missing operator");
+    assert_eq!(error("value = x {{ 1\n"), Err("1:11: expression `{{` is never closed (in `value`)".to_string()), "Failed to report an unclosed expression");
+    assert_eq!(error("value = {{ 9223372036854775807 + 1 }}\n"), Err("1:12: the result does not fit into a 64 bit integer (in `value`)".to_string()), "Failed to report an overflow");
+
+    let err = serde_hyprlang::from_str::<Single>("value = {{ 4 / (2 - 2) }}\n").expect_err("Failed to report a division by zero");
+    assert_eq!(err.location().map(|l| l.span.clone()), Some(11..22), "Failed to span the whole division");
+}
+
+#[test]
+fn eval_public() {
+    let variable = |name: &str| match name {
+        "gap" => Some("4".to_string()),
+        "scale" => Some(" 1.5 ".to_string()),
+        _ => None
+    };
+
+    assert_eq!(serde_hyprlang::eval("$gap * $scale", variable), Ok(Number::Float(6.0)), "Failed to mix integers and floats");
+    assert_eq!(serde_hyprlang::eval("-(gap + 3) / 2", variable), Ok(Number::Int(-3)), "Failed to divide integers");
+    assert_eq!(Number::Float(6.0).to_string(), "6", "Failed to display a float");
+    assert_eq!(serde_hyprlang::eval("2.5e-1 + 1E+1 - 1e1", variable), Ok(Number::Float(0.25)), "Failed to read exponents with a sign");
+    assert_eq!(serde_hyprlang::eval("2e-1-1", variable), Ok(Number::Float(-0.8)), "Failed to subtract after an exponent");
+
+    let err = serde_hyprlang::eval("gap / 0", variable).expect_err("Failed to report a division by zero");
+    assert_eq!(err.location().map(|l| (l.line, l.column, l.span.clone())), Some((1, 1, 0..7)), "Failed to locate the error within the expression");
+}
+
+#[test]
+fn expression_depth() {
+    let variable = |_: &str| None;
+
+    let nested = format!("{}1{}", "(".repeat(256), ")".repeat(256));
+    assert_eq!(serde_hyprlang::eval(&nested, variable), Ok(Number::Int(1)), "Failed to evaluate nested parentheses");
+
+    let err = serde_hyprlang::eval(&"(".repeat(100000), variable).expect_err("Failed to report parentheses nested too deeply");
+    assert_eq!(err.location().map(|l| l.span.clone()), Some(256..257), "Failed to span the parenthesis that is too deep");
+    assert_eq!(serde_hyprlang::eval(&"-".repeat(100000), variable).map_err(|e| e.to_string()), Err("1:257: expressions can not be nested more than 256 levels deep".to_string()), "Failed to report negations nested too deeply");
+
+    let t = format!("value = {{{{ {}1 }}}}\n", "(".repeat(100000));
+    let res = serde_hyprlang::from_str::<Single>(&t).map_err(|e| e.to_string());
+    assert_eq!(res, Err("1:268: expressions can not be nested more than 256 levels deep (in `value`)".to_string()), "Failed to report nesting within a value");
+}
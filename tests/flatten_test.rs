@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_hyprlang::Inferred;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Shadow {
+    enabled: bool,
+    range: u16
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Border {
+    border_size: u8,
+    rounding: i32,
+    shadow: Shadow
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Decoration {
+    active_opacity: f32,
+    #[serde(flatten)]
+    border: Border
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Root {
+    decoration: Decoration,
+    #[serde(flatten)]
+    general: Decoration
+}
+
+#[test]
+fn flatten_roundtrip() {
+    let t =
+"decoration {
+    active_opacity = 0.5
+    border_size = 2
+    rounding = -10
+    shadow {
+        enabled = yes
+        range = 0x10
+    }
+}
+active_opacity = 1
+border_size = 0
+rounding = 0
+shadow:enabled = false
+shadow:range = 4
+";
+    let res = serde_hyprlang::from_str(t);
+
+    let expect = Root {
+        decoration: Decoration {
+            active_opacity: 0.5,
+            border: Border { border_size: 2, rounding: -10, shadow: Shadow { enabled: true, range: 16 } }
+        },
+        general: Decoration {
+            active_opacity: 1.0,
+            border: Border { border_size: 0, rounding: 0, shadow: Shadow { enabled: false, range: 4 } }
+        }
+    };
+
+    assert_eq!(res, Ok(expect.clone()), "Failed to decode flattened structs");
+
+    let res = serde_hyprlang::to_string(&expect);
+
+    let text =
+"decoration {
+    active_opacity = 0.5
+    border_size = 2
+    rounding = -10
+    shadow {
+        enabled = true
+        range = 16
+    }
+
+}
+
+active_opacity = 1
+border_size = 0
+rounding = 0
+shadow {
+    enabled = false
+    range = 4
+}
+
+".to_string();
+
+    assert_eq!(res, Ok(text.clone()), "Failed to encode flattened structs");
+    assert_eq!(serde_hyprlang::from_str(&text), Ok(expect), "Failed to roundtrip flattened structs");
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct CatchAll<T> {
+    gaps_in: u8,
+    #[serde(flatten)]
+    rest: HashMap<String, T>
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Named {
+    name: String
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct FlatNamed {
+    #[serde(flatten)]
+    named: Named
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Execs {
+    exec: Vec<String>
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct FlatExecs {
+    #[serde(flatten)]
+    execs: Execs
+}
+
+#[test]
+fn flatten_inferred_types() {
+    let t = "gaps_in = 5\nlayout = dwindle\ngaps_out = 10\n";
+
+    // The types are inferred from the text, so numbers do not become strings
+    let res = serde_hyprlang::from_str::<CatchAll<String>>(t).map_err(|e| e.to_string());
+    assert_eq!(res, Err("invalid type: integer `10`, expected a string".to_string()), "Failed to infer a number within a flattened map");
+
+    let res = serde_hyprlang::from_str::<FlatNamed>("name = 1\n").map_err(|e| e.to_string());
+    assert_eq!(res, Err("invalid type: integer `1`, expected a string".to_string()), "Failed to infer a number within a flattened struct");
+
+    // Only repeated values become a sequence
+    let res = serde_hyprlang::from_str::<FlatExecs>("exec = kitty\n").map_err(|e| e.to_string());
+    assert_eq!(res, Err("invalid type: string \"kitty\", expected a sequence".to_string()), "Failed to infer a single value within a flattened struct");
+    let res = serde_hyprlang::from_str::<FlatExecs>("exec = kitty\nexec = waybar\n");
+    assert_eq!(res, Ok(FlatExecs { execs: Execs { exec: vec!["kitty".to_string(), "waybar".to_string()] } }), "Failed to read repeated values within a flattened struct");
+
+    // `Inferred` takes any value
+    let res = serde_hyprlang::from_str::<CatchAll<Inferred>>(t);
+    let expect = CatchAll {
+        gaps_in: 5,
+        rest: HashMap::from([("layout".to_string(), Inferred::new("dwindle")), ("gaps_out".to_string(), Inferred::new("10"))])
+    };
+    assert_eq!(res, Ok(expect), "Failed to read a flattened map of inferred values");
+}
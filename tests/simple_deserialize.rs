@@ -0,0 +1,513 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SimpleStruct {
+    first: String,
+    second: (bool, usize)
+}
+
+#[test]
+fn simple_struct_deserialize() {
+    let t = "first = Vaxry\nsecond = true, 1\n";
+    let res = serde_hyprlang::from_str(t);
+
+    let expect = SimpleStruct {
+        first: "Vaxry".to_string(),
+        second: (true, 1)
+    };
+
+    assert_eq!(res, Ok(expect), "Failed to decode a simple struct");
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SimpleSequence {
+    exec: Vec<(usize, String)>
+}
+
+#[test]
+fn simple_sequence_roundtrip() {
+    let t = SimpleSequence {
+        exec: vec![(4, "Hewo".to_string()), (1, "everynya, with a comma".to_string())]
+    };
+    let res = serde_hyprlang::from_str(&serde_hyprlang::to_string(&t).unwrap());
+
+    assert_eq!(res, Ok(t), "Failed to roundtrip a simple sequence");
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SimpleStructSequence {
+    random_thing: i8,
+    keep_it_simple_stupid: Vec<SimpleStruct>
+}
+
+#[test]
+fn simple_sequence_struct_roundtrip() {
+    let t = SimpleStructSequence {
+        keep_it_simple_stupid: vec![
+            SimpleStruct {
+                first: "I wish".to_string(),
+                second: (true, 3)
+            },
+            SimpleStruct {
+                first: "I were".to_string(),
+                second: (true, 1)
+            },
+            SimpleStruct {
+                first: "a bird".to_string(),
+                second: (false, 0)
+            },
+        ],
+        random_thing: 120
+    };
+    let res = serde_hyprlang::from_str(&serde_hyprlang::to_string(&t).unwrap());
+
+    assert_eq!(res, Ok(t), "Failed to roundtrip a simple sequence of structs");
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Strings {
+    v: Vec<String>
+}
+
+#[test]
+fn empty_sequence_roundtrip() {
+    let t = SimpleSequence { exec: vec![] };
+    let res = serde_hyprlang::from_str(&serde_hyprlang::to_string(&t).unwrap());
+    assert_eq!(res, Ok(t), "Failed to roundtrip an empty sequence");
+
+    let t = SimpleStructSequence { random_thing: 3, keep_it_simple_stupid: vec![] };
+    let res = serde_hyprlang::from_str(&serde_hyprlang::to_string(&t).unwrap());
+    assert_eq!(res, Ok(t), "Failed to roundtrip an empty sequence of structs");
+
+    let t = Strings { v: vec!["".to_string()] };
+    let res = serde_hyprlang::from_str(&serde_hyprlang::to_string(&t).unwrap());
+    assert_eq!(res, Ok(t), "Failed to roundtrip a sequence of an empty string");
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SimpleNestedStruct {
+    config: SimpleStruct,
+    other: bool
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SimpleNestedRootStruct {
+    test: usize,
+    nested: SimpleNestedStruct,
+}
+
+#[test]
+fn simple_nested_struct_roundtrip() {
+    let t = SimpleNestedRootStruct {
+        nested: SimpleNestedStruct {
+            other: true,
+            config: SimpleStruct {
+                first: "Oh my God!".to_string(),
+                second: (false, 64)
+            },
+        },
+        test: 6732
+    };
+    let res = serde_hyprlang::from_str(&serde_hyprlang::to_string(&t).unwrap());
+
+    assert_eq!(res, Ok(t), "Failed to roundtrip a simple nesting of structs");
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SimpleOptionEncoding {
+    here: Option<isize>,
+    not_here: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    omit: Option<bool>,
+
+    not_st: Option<SimpleStruct>,
+    st: Option<SimpleStruct>,
+
+    inside_tupple: (bool, Option<String>, bool),
+    inside_tupple_2: (Option<bool>, Option<String>, bool),
+}
+
+#[test]
+fn simple_option_roundtrip() {
+    let t = SimpleOptionEncoding {
+        here: Some(-23),
+        not_here: None,
+
+        not_st: None,
+        st: Some(SimpleStruct {
+            first: "Not another one...".to_string(),
+            second: (true, 777)
+        }),
+
+        inside_tupple: (false, None, true),
+        inside_tupple_2: (None, Some("Text".to_string()), false),
+
+        omit: None
+    };
+    let res = serde_hyprlang::from_str(&serde_hyprlang::to_string(&t).unwrap());
+
+    assert_eq!(res, Ok(t), "Failed to roundtrip options and their nulls");
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum SimpleUnitEnum {
+    Dwindle,
+    Master
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SimpleEnumStruct {
+    layout: SimpleUnitEnum,
+    nested: ((u8, i64), String)
+}
+
+#[test]
+fn simple_unit_enum_and_nested_tupple_roundtrip() {
+    let t = SimpleEnumStruct {
+        layout: SimpleUnitEnum::Master,
+        nested: ((3, -4), "exec, kitty".to_string())
+    };
+    let res = serde_hyprlang::from_str(&serde_hyprlang::to_string(&t).unwrap());
+
+    assert_eq!(res, Ok(t), "Failed to roundtrip unit enums and nested tupples");
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum SimpleEnumHandling {
+    NewTypeVarient(SimpleStruct),
+    TuppleVarient(usize, bool),
+    StructVarient{ thing: f64 },
+    Root{ new_type: Box<SimpleEnumHandling>, tupple: Box<SimpleEnumHandling>, st: Box<SimpleEnumHandling>}
+}
+
+#[test]
+fn simple_enum_roundtrip() {
+    let t = SimpleEnumHandling::Root {
+        new_type: Box::new(SimpleEnumHandling::NewTypeVarient(
+            SimpleStruct {
+                first: "REDNote 小红书国际版".to_string(),
+                second: (false, 1989)
+            }
+        )),
+        tupple: Box::new(SimpleEnumHandling::TuppleVarient(75, false)),
+        st: Box::new(SimpleEnumHandling::StructVarient { thing: 5.56 })
+    };
+
+    // Untagged enums, the default, do not write their variant, so the output can not be read back
+    let res = serde_hyprlang::from_str::<SimpleEnumHandling>(&serde_hyprlang::to_string(&t).unwrap()).map_err(|e| e.to_string());
+    assert_eq!(res, Err("6:1: expected a single variant of `SimpleEnumHandling`, found `new_type` and `tupple`".to_string()), "Failed to reject untagged enums");
+
+    let ser = serde_hyprlang::Serializer::new().enum_tagging(serde_hyprlang::EnumTagging::Key);
+    let res = serde_hyprlang::from_str(&serde_hyprlang::to_string_with(&t, ser).unwrap());
+    assert_eq!(res, Ok(t), "Failed to roundtrip the different handled enums");
+}
+
+#[test]
+fn simple_syntax_error() {
+    let t = "general {\n    border_size = 2\n";
+    let res = serde_hyprlang::from_str::<SimpleStruct>(t).map_err(|e| e.to_string());
+
+    assert_eq!(res, Err("1:1: category `general` is never closed (in `general`)".to_string()), "Failed to report an unclosed category");
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Blur {
+    size: u8,
+    passes: u8
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Decoration {
+    rounding: u8,
+    blur: Blur
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct ColonConfig {
+    decoration: Decoration
+}
+
+#[test]
+fn colon_path_deserialize() {
+    let t =
+"decoration:blur:size = 8
+decoration {
+    blur {
+        passes = 2
+    }
+}
+decoration:rounding = 10
+";
+    let res = serde_hyprlang::from_str(t);
+
+    let expect = ColonConfig {
+        decoration: Decoration {
+            rounding: 10,
+            blur: Blur { size: 8, passes: 2 }
+        }
+    };
+
+    assert_eq!(res, Ok(expect), "Failed to merge colon paths with categories");
+}
+
+#[test]
+fn colon_path_roundtrip() {
+    let t = SimpleStructSequence {
+        keep_it_simple_stupid: vec![
+            SimpleStruct {
+                first: "I wish".to_string(),
+                second: (true, 3)
+            },
+            SimpleStruct {
+                first: "I were".to_string(),
+                second: (true, 1)
+            },
+        ],
+        random_thing: 120
+    };
+    let text = serde_hyprlang::to_string_with(&t, serde_hyprlang::Serializer::new().colon_paths(usize::MAX)).unwrap();
+    let res = serde_hyprlang::from_str(&text);
+
+    assert_eq!(res, Ok(t), "Failed to roundtrip colon paths");
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SimpleMaps {
+    scalars: std::collections::HashMap<String, i32>,
+    structs: std::collections::BTreeMap<u16, SimpleStruct>,
+}
+
+#[test]
+fn simple_map_roundtrip() {
+    let t = SimpleMaps {
+        scalars: [("b".to_string(), 2), ("c".to_string(), 3), ("a".to_string(), 1)].into_iter().collect(),
+        structs: [
+            (2, SimpleStruct { first: "Two".to_string(), second: (false, 2) }),
+            (1, SimpleStruct { first: "One".to_string(), second: (true, 1) }),
+        ].into_iter().collect(),
+    };
+    let res = serde_hyprlang::from_str(&serde_hyprlang::to_string(&t).unwrap());
+
+    assert_eq!(res, Ok(t), "Failed to roundtrip maps");
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SimpleDevice {
+    name: String,
+    sensitivity: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SimpleSpecialCategories {
+    #[serde(rename = "device[name]")]
+    device: std::collections::HashMap<String, SimpleDevice>,
+}
+
+#[test]
+fn special_category_deserialize() {
+    let t =
+"device {
+    name = my-mouse
+    sensitivity = -0.5
+}
+device[epic-keyboard] {
+    sensitivity = 1
+}
+device:my-mouse:sensitivity = -0.25
+";
+    let res = serde_hyprlang::from_str(t);
+
+    let expect = SimpleSpecialCategories {
+        device: [
+            ("my-mouse".to_string(), SimpleDevice { name: "my-mouse".to_string(), sensitivity: -0.25 }),
+            ("epic-keyboard".to_string(), SimpleDevice { name: "epic-keyboard".to_string(), sensitivity: 1.0 }),
+        ].into_iter().collect(),
+    };
+
+    assert_eq!(res, Ok(expect), "Failed to decode special categories");
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SimpleDevices {
+    device: std::collections::BTreeMap<String, SimpleDevice>,
+}
+
+#[test]
+fn special_category_option_roundtrip() {
+    let t = SimpleDevices {
+        device: [
+            ("a".to_string(), SimpleDevice { name: "a".to_string(), sensitivity: 0.5 }),
+            ("b".to_string(), SimpleDevice { name: "b".to_string(), sensitivity: 2.0 }),
+        ].into_iter().collect(),
+    };
+    let text = serde_hyprlang::to_string_with(&t, serde_hyprlang::Serializer::new().special_category_key("name")).unwrap();
+    let res = SimpleDevices::deserialize(&mut serde_hyprlang::Deserializer::from_str(&text).special_category_key("name"));
+
+    assert_eq!(res, Ok(t), "Failed to roundtrip special categories");
+}
+
+#[test]
+fn comment_deserialize() {
+    let t =
+"# The first key
+first = ##33ccff # a color ## with a comment
+# second = false, 0
+second = true, 1# trailing
+";
+    let res = serde_hyprlang::from_str(t);
+
+    let expect = SimpleStruct {
+        first: "#33ccff".to_string(),
+        second: (true, 1)
+    };
+
+    assert_eq!(res, Ok(expect), "Failed to strip comments and unescape ##");
+
+    let t = SimpleStruct {
+        first: "https://example.com/#anchor, ## and more".to_string(),
+        second: (false, 2)
+    };
+    let res = serde_hyprlang::from_str(&serde_hyprlang::to_string(&t).unwrap());
+
+    assert_eq!(res, Ok(t), "Failed to roundtrip a # in a string");
+}
+
+#[test]
+fn quoting_roundtrip() {
+    let t = SimpleSequence {
+        exec: vec![
+            (1, " padded ".to_string()),
+            (2, "say \"hi\", then\nleave\ttabbed".to_string()),
+            (3, "\"starts with a quote".to_string()),
+            (4, "ends with a backslash\\, in quotes\\".to_string()),
+            (5, "rgb(33ccff) # not a comment, \"quoted\"".to_string()),
+        ]
+    };
+    let res = serde_hyprlang::from_str(&serde_hyprlang::to_string(&t).unwrap());
+
+    assert_eq!(res, Ok(t), "Failed to roundtrip quoted strings");
+
+    let t = "exec = 1, \"only\" partially quoted\n";
+    let res = serde_hyprlang::from_str(t);
+
+    let expect = SimpleSequence {
+        exec: vec![(1, "\"only\" partially quoted".to_string())]
+    };
+
+    assert_eq!(res, Ok(expect), "Failed to keep text that is not entirely quoted");
+}
+
+#[test]
+fn expansion_escape_roundtrip() {
+    let t = SimpleSequence {
+        exec: vec![
+            (1, "$HOME".to_string()),
+            (2, "cost $5".to_string()),
+            (3, "{{ 1 + 1 }}".to_string()),
+            (4, "a backslash \\$HOME and \\{{ 2 }}".to_string()),
+            (5, "$HOME, {{ 1 + 1 }}, quoted".to_string()),
+        ]
+    };
+    let res = serde_hyprlang::to_string(&t);
+    assert_eq!(res.as_ref().map(|t| t.lines().nth(2)), Ok(Some("exec = 3, \\{{ 1 + 1 }}")), "Failed to escape an expression");
+
+    let res = serde_hyprlang::from_str(&res.unwrap());
+    assert_eq!(res, Ok(t), "Failed to roundtrip strings with variables and expressions");
+}
+
+#[test]
+fn root_value_roundtrip() {
+    for t in ["a # b", "$HOME", "cost $5", "{{ 1 + 1 }}", "##, \\$x"] {
+        let res = serde_hyprlang::from_str::<String>(&serde_hyprlang::to_string(&t).unwrap());
+        assert_eq!(res.as_deref(), Ok(t), "Failed to roundtrip a string at the root");
+    }
+}
+
+#[test]
+fn root_value_comment_deserialize() {
+    assert_eq!(serde_hyprlang::from_str::<i32>("5 # note\n"), Ok(5), "Failed to ignore a comment after a value at the root");
+    assert_eq!(serde_hyprlang::from_str::<serde_hyprlang::Vec2>("1 2 # note"), Ok(serde_hyprlang::Vec2(1.0, 2.0)), "Failed to ignore a comment after a vec2 at the root");
+    assert_eq!(serde_hyprlang::from_str::<String>("a ## b # note"), Ok("a # b".to_string()), "Failed to unescape a `##` at the root");
+    assert_eq!(serde_hyprlang::from_str::<(String, u8)>("# header\nkitty, \\\n    2\n"), Ok(("kitty".to_string(), 2)), "Failed to continue a value at the root");
+}
+
+#[test]
+fn line_continuation_deserialize() {
+    let t =
+"exec = 1, waybar --config \\
+    ~/.config/waybar/config.jsonc \\ # the style follows
+    --style style.css
+exec = \\
+    2, done
+";
+    let res = serde_hyprlang::from_str(t);
+
+    let expect = SimpleSequence {
+        exec: vec![
+            (1, "waybar --config ~/.config/waybar/config.jsonc --style style.css".to_string()),
+            (2, "done".to_string()),
+        ]
+    };
+
+    assert_eq!(res, Ok(expect), "Failed to join continued lines");
+
+    let t = "first = a\nsecond = true, \\\n    many\n";
+    let res = serde_hyprlang::from_str::<SimpleStruct>(t).map_err(|e| e.to_string());
+
+    assert_eq!(res, Err("3:5: expected an unsigned integer, found `many`: invalid digit found in string (in `second`)".to_string()), "Failed to locate an error on a continued line");
+
+    let t = SimpleSequence {
+        exec: vec![
+            (1, "waybar --config ~/.config/waybar/config.jsonc --style style.css".to_string()),
+            (2, "ends with a backslash\\".to_string()),
+            (3, "a value   with ## wide   gaps, and a \"quote\" that is long".to_string()),
+        ]
+    };
+    let text = serde_hyprlang::to_string_with(&t, serde_hyprlang::Serializer::new().wrap(20)).unwrap();
+    let res = serde_hyprlang::from_str(&text);
+
+    assert_eq!(res, Ok(t), "Failed to roundtrip wrapped values");
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Matcher {
+    class: String,
+    title: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct WindowRules {
+    windowrulev2: Vec<(String, Matcher)>,
+    matcher: Matcher,
+    nested: ((Matcher, u8), bool),
+}
+
+#[test]
+fn inline_struct_deserialize() {
+    let t =
+"windowrulev2 = float, class:^(kitty)$
+windowrulev2 = opacity 0.9, title:^(foo: bar)$, class: \"^(a, b)$\"
+matcher = class:firefox
+nested = class:code, 3, true
+";
+    let res = serde_hyprlang::from_str(t);
+
+    let expect = WindowRules {
+        windowrulev2: vec![
+            ("float".to_string(), Matcher { class: "^(kitty)$".to_string(), title: None }),
+            ("opacity 0.9".to_string(), Matcher { class: "^(a, b)$".to_string(), title: Some("^(foo: bar)$".to_string()) }),
+        ],
+        matcher: Matcher { class: "firefox".to_string(), title: None },
+        nested: ((Matcher { class: "code".to_string(), title: None }, 3), true),
+    };
+
+    assert_eq!(res, Ok(expect.clone()), "Failed to decode inline structs");
+
+    let res = serde_hyprlang::from_str(&serde_hyprlang::to_string(&expect).unwrap());
+    assert_eq!(res, Ok(expect), "Failed to roundtrip inline structs");
+
+    let t = "windowrulev2 = float, title:x\nmatcher = class:a, size:5\nnested = class:code, 3, true\n";
+    let res = serde_hyprlang::from_str::<WindowRules>(t).map_err(|e| e.to_string());
+    assert_eq!(res, Err("1:23: missing field `class` (in `windowrulev2`)".to_string()), "Failed to report a missing inline field");
+}
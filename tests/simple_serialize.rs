@@ -218,3 +218,286 @@ st {
 
     assert_eq!(res, Ok(expect), "Failed to encode the different handled enums correctly");
 }
+
+#[test]
+fn colon_path_serializer() {
+    let t = SimpleNestedRootStruct {
+        nested: SimpleNestedStruct {
+            other: true,
+            config: SimpleStruct {
+                first: "Flat".to_string(),
+                second: (false, 64)
+            },
+        },
+        test: 6732
+    };
+    let res = serde_hyprlang::to_string_with(&t, serde_hyprlang::Serializer::new().colon_paths(usize::MAX));
+    
+    let expect = 
+"test = 6732
+nested:config:first = Flat
+nested:config:second = false, 64
+nested:other = true
+".to_string();
+
+    assert_eq!(res, Ok(expect), "Failed to encode nested structs as colon paths");
+}
+
+#[test]
+fn colon_path_threshold_serializer() {
+    let t = SimpleStructSequence {
+        keep_it_simple_stupid: vec![
+            SimpleStruct {
+                first: "Blocks".to_string(),
+                second: (true, 3)
+            },
+        ],
+        random_thing: 120
+    };
+    let res = serde_hyprlang::to_string_with(&t, serde_hyprlang::Serializer::new().colon_paths(2));
+
+    let expect = 
+"random_thing = 120
+keep_it_simple_stupid {
+    first = Blocks
+    second = true, 3
+}
+
+
+".to_string();
+
+    assert_eq!(res, Ok(expect), "Failed to keep sequences of structs as blocks");
+
+    let t = SimpleNestedRootStruct {
+        nested: SimpleNestedStruct {
+            other: false,
+            config: SimpleStruct {
+                first: "Small".to_string(),
+                second: (true, 2)
+            },
+        },
+        test: 1
+    };
+    let res = serde_hyprlang::to_string_with(&t, serde_hyprlang::Serializer::new().colon_paths(1));
+
+    let expect = 
+"test = 1
+nested {
+    config {
+        first = Small
+        second = true, 2
+    }
+
+    other = false
+}
+
+".to_string();
+
+    assert_eq!(res, Ok(expect), "Failed to keep categories above the threshold as blocks");
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct SimpleMaps {
+    scalars: std::collections::HashMap<String, i32>,
+    structs: std::collections::BTreeMap<String, SimpleStruct>,
+}
+
+#[test]
+fn simple_map_serialize() {
+    let t = SimpleMaps {
+        scalars: [("b".to_string(), 2), ("c".to_string(), 3), ("a".to_string(), 1)].into_iter().collect(),
+        structs: [
+            ("second".to_string(), SimpleStruct { first: "Two".to_string(), second: (false, 2) }),
+            ("first".to_string(), SimpleStruct { first: "One".to_string(), second: (true, 1) }),
+        ].into_iter().collect(),
+    };
+    let res = serde_hyprlang::to_string(&t);
+
+    let expect = 
+"scalars {
+    a = 1
+    b = 2
+    c = 3
+}
+
+structs {
+    first {
+        first = One
+        second = true, 1
+    }
+
+    second {
+        first = Two
+        second = false, 2
+    }
+
+}
+
+".to_string();
+
+    assert_eq!(res, Ok(expect), "Failed to encode maps of scalars and structs");
+}
+
+#[test]
+fn numeric_key_map_serialize() {
+    let t: std::collections::HashMap<i32, &str> = [(10, "ten"), (2, "two"), (-1, "minus one"), (1, "one")].into_iter().collect();
+    let res = serde_hyprlang::to_string(&t);
+
+    let expect =
+"-1 = minus one
+1 = one
+2 = two
+10 = ten
+".to_string();
+
+    assert_eq!(res, Ok(expect), "Failed to order numeric keys by their number");
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct SimpleDevice {
+    name: String,
+    sensitivity: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct SimpleSpecialCategories {
+    #[serde(rename = "device[name]")]
+    device: std::collections::HashMap<String, SimpleDevice>,
+}
+
+#[test]
+fn special_category_serialize() {
+    let t = SimpleSpecialCategories {
+        device: [
+            ("my-mouse".to_string(), SimpleDevice { name: "my-mouse".to_string(), sensitivity: -0.5 }),
+            ("epic-keyboard".to_string(), SimpleDevice { name: "epic-keyboard".to_string(), sensitivity: 1.0 }),
+        ].into_iter().collect(),
+    };
+    let res = serde_hyprlang::to_string(&t);
+
+    let expect = 
+"device {
+    name = epic-keyboard
+    sensitivity = 1
+}
+
+device {
+    name = my-mouse
+    sensitivity = -0.5
+}
+
+".to_string();
+
+    assert_eq!(res, Ok(expect), "Failed to encode a map as special categories");
+}
+
+#[test]
+fn special_category_mismatch_serialize() {
+    let t = SimpleSpecialCategories {
+        device: [("m".to_string(), SimpleDevice { name: "other".to_string(), sensitivity: 0.0 })].into_iter().collect(),
+    };
+    let res = serde_hyprlang::to_string(&t).map_err(|e| e.to_string());
+
+    assert_eq!(res, Err("the `name` field is `other`, but the map key of its category is `m`".to_string()), "Failed to report a key field that differs from the map key");
+}
+
+#[test]
+fn special_category_option_serialize() {
+    let t = SimpleMaps {
+        scalars: [("a".to_string(), 1)].into_iter().collect(),
+        structs: [
+            ("first".to_string(), SimpleStruct { first: "One".to_string(), second: (true, 1) }),
+        ].into_iter().collect(),
+    };
+    let res = serde_hyprlang::to_string_with(&t, serde_hyprlang::Serializer::new().special_category_key("name"));
+
+    let expect = 
+"scalars:a = 1
+structs {
+    name = first
+    first = One
+    second = true, 1
+}
+
+".to_string();
+
+    assert_eq!(res, Ok(expect), "Failed to encode maps with a special category key");
+}
+
+#[test]
+fn hash_escape_serialize() {
+    let h = SimpleStruct {
+        first: "#33ccff".to_string(),
+        second: (true, 1)
+    };
+
+    let res = serde_hyprlang::to_string(&h);
+    
+    let expect = "first = ##33ccff\nsecond = true, 1\n".to_string();
+
+    assert_eq!(res, Ok(expect), "Failed to escape a #");
+}
+
+#[test]
+fn quoting_serialize() {
+    let q = SimpleSequence {
+        exec: vec![
+            (1, "plain text".to_string()),
+            (2, " padded".to_string()),
+            (3, "say \"hi\", then\nleave".to_string()),
+            (4, "C:\\no\\quotes".to_string()),
+        ]
+    };
+
+    let res = serde_hyprlang::to_string(&q);
+
+    let expect =
+"exec = 1, plain text
+exec = 2, \" padded\"
+exec = 3, \"say \\\"hi\\\", then\\nleave\"
+exec = 4, C:\\no\\quotes
+
+".to_string();
+
+    assert_eq!(res, Ok(expect), "Failed to quote and escape strings");
+}
+
+#[test]
+fn invalid_key_serialize() {
+    let mut scalars = std::collections::HashMap::new();
+    scalars.insert("gaps in".to_string(), 5);
+
+    let m = SimpleMaps {
+        scalars,
+        structs: std::collections::BTreeMap::new()
+    };
+
+    let res = serde_hyprlang::to_string(&m);
+
+    assert_eq!(res, Err(serde_hyprlang::Error::InvalidKey("gaps in".to_string())), "Failed to reject a key with whitespace");
+}
+
+#[test]
+fn wrap_serialize() {
+    let q = SimpleSequence {
+        exec: vec![
+            (1, "waybar --config ~/.config/waybar/config.jsonc --style style.css".to_string()),
+            (2, "short".to_string()),
+            (3, "~/.config/hypr/scripts/a-very-long-script-name-without-spaces.sh".to_string()),
+        ]
+    };
+
+    let res = serde_hyprlang::to_string_with(&q, serde_hyprlang::Serializer::new().wrap(30));
+
+    let expect =
+"exec = 1, waybar --config \\
+    ~/.config/waybar/config.jsonc \\
+    --style style.css
+exec = 2, short
+exec = 3, \\
+    ~/.config/hypr/scripts/a-very-long-script-name-without-spaces.sh
+
+".to_string();
+
+    assert_eq!(res, Ok(expect), "Failed to wrap long values");
+}
@@ -0,0 +1,162 @@
+use serde::Deserialize;
+use serde_hyprlang::{Error, VirtualFileSystem};
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct General {
+    gaps_in: u8,
+    gaps_out: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Config {
+    general: General,
+    bind: Vec<(String, String, String)>,
+}
+
+#[test]
+fn source_include() {
+    let fs = VirtualFileSystem::new()
+        .home("/home/user")
+        .file("/home/user/.config/hypr/binds.conf", "bind = $mod, Q, killactive\nsource = ./more/binds.conf\n")
+        .file("/home/user/.config/hypr/more/binds.conf", "bind = $mod, F, fullscreen\n")
+        .file("/home/user/.config/hypr/general.conf", "general:gaps_out = 10\n");
+
+    let t =
+"$mod = SUPER
+source = binds.conf
+general {
+    gaps_in = 5
+}
+source = ~/.config/hypr/general.conf
+";
+    let mut de = serde_hyprlang::Deserializer::from_str(t)
+        .file_name("/home/user/.config/hypr/hyprland.conf")
+        .resolver(fs);
+    let res = Config::deserialize(&mut de);
+
+    let expect = Config {
+        general: General { gaps_in: 5, gaps_out: 10 },
+        bind: vec![
+            ("SUPER".to_string(), "Q".to_string(), "killactive".to_string()),
+            ("SUPER".to_string(), "F".to_string(), "fullscreen".to_string()),
+        ]
+    };
+
+    assert_eq!(res, Ok(expect), "Failed to follow source includes");
+}
+
+#[test]
+fn source_errors() {
+    let fs = VirtualFileSystem::new()
+        .file("/hypr/a.conf", "source = b.conf\n")
+        .file("/hypr/b.conf", "source = a.conf\n")
+        .file("/hypr/broken.conf", "general {\n    gaps_in = 5\n");
+
+    let mut de = serde_hyprlang::Deserializer::from_str("source = a.conf\n").file_name("/hypr/main.conf").resolver(fs.clone());
+    let res = Config::deserialize(&mut de).map_err(|e| e.to_string());
+    assert_eq!(res, Err("/hypr/b.conf:1:10: `/hypr/a.conf` includes itself: /hypr/main.conf -> /hypr/a.conf -> /hypr/b.conf -> /hypr/a.conf (in `source`)".to_string()), "Failed to detect an include cycle");
+
+    let mut de = serde_hyprlang::Deserializer::from_str("\nsource = missing.conf\n").file_name("/hypr/main.conf").resolver(fs.clone());
+    let res = Config::deserialize(&mut de).map_err(|e| e.to_string());
+    assert_eq!(res, Err("/hypr/main.conf:2:10: can not read `/hypr/missing.conf`: no such file (in `source`)".to_string()), "Failed to report a missing include");
+
+    let mut de = serde_hyprlang::Deserializer::from_str("source = broken.conf\n}\n").file_name("/hypr/main.conf").resolver(fs);
+    let res = Config::deserialize(&mut de);
+    assert_eq!(res.as_ref().err().and_then(Error::location).and_then(|l| l.file.clone()), Some("/hypr/broken.conf".to_string()), "Failed to report the file of an error");
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Plain {
+    source: String,
+}
+
+#[test]
+fn source_without_resolver() {
+    let res = serde_hyprlang::from_str("source = ~/binds.conf\n");
+
+    assert_eq!(res, Ok(Plain { source: "~/binds.conf".to_string() }), "Failed to read source as a key without a resolver");
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Nested {
+    plugin: Plain,
+}
+
+#[test]
+fn source_within_category() {
+    let fs = VirtualFileSystem::new().file("/hypr/binds.conf", "bind = SUPER, Q, killactive\n");
+    let mut de = serde_hyprlang::Deserializer::from_str("plugin {\n    source = /hypr/binds.conf\n}\n").resolver(fs);
+    let res = Nested::deserialize(&mut de);
+
+    assert_eq!(res, Ok(Nested { plugin: Plain { source: "/hypr/binds.conf".to_string() } }), "Failed to read source within a category as a key");
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Binds {
+    bind: Vec<(String, String, String)>,
+}
+
+#[test]
+fn source_glob() {
+    let fs = VirtualFileSystem::new()
+        .file("/hypr/conf.d/20-apps.conf", "bind = SUPER, E, nautilus\n")
+        .file("/hypr/conf.d/10-base.conf", "bind = SUPER, Q, killactive\n")
+        .file("/hypr/conf.d/.hidden.conf", "bind = SUPER, H, hidden\n")
+        .file("/hypr/conf.d/notes.txt", "bind = SUPER, N, notes\n")
+        .file("/hypr/machines/laptop/extra.conf", "bind = SUPER, L, laptop\n")
+        .file("/hypr/machines/desktop/extra.conf", "bind = SUPER, D, desktop\n");
+
+    let t = "source = conf.d/*.conf\nsource = machines/[dl]*/extra.conf\n";
+    let mut de = serde_hyprlang::Deserializer::from_str(t).file_name("/hypr/hyprland.conf").resolver(fs);
+    let res = Binds::deserialize(&mut de);
+
+    let expect = Binds {
+        bind: vec![
+            ("SUPER".to_string(), "Q".to_string(), "killactive".to_string()),
+            ("SUPER".to_string(), "E".to_string(), "nautilus".to_string()),
+            ("SUPER".to_string(), "D".to_string(), "desktop".to_string()),
+            ("SUPER".to_string(), "L".to_string(), "laptop".to_string()),
+        ]
+    };
+
+    assert_eq!(res, Ok(expect), "Failed to include globs in sorted order");
+}
+
+#[test]
+fn source_empty_glob() {
+    let fs = VirtualFileSystem::new().file("/hypr/binds.conf", "bind = SUPER, Q, killactive\n");
+    let t = "source = binds.conf\nsource = conf.d/*.conf\n";
+
+    let mut de = serde_hyprlang::Deserializer::from_str(t).file_name("/hypr/hyprland.conf").resolver(fs.clone());
+    let res = Binds::deserialize(&mut de).map_err(|e| e.to_string());
+    assert_eq!(res, Err("/hypr/hyprland.conf:2:10: `/hypr/conf.d/*.conf` matches no file (in `source`)".to_string()), "Failed to reject an empty glob");
+
+    let mut de = serde_hyprlang::Deserializer::from_str(t).file_name("/hypr/hyprland.conf").resolver(fs).allow_empty_globs();
+    let res = Binds::deserialize(&mut de);
+    assert_eq!(res, Ok(Binds { bind: vec![("SUPER".to_string(), "Q".to_string(), "killactive".to_string())] }), "Failed to allow an empty glob");
+    assert_eq!(de.warnings().iter().map(|w| w.inner().to_string()).collect::<Vec<_>>(), vec!["`/hypr/conf.d/*.conf` matches no file".to_string()], "Failed to warn about an empty glob");
+}
+
+#[test]
+fn source_glob_filesystem() {
+    let dir = std::env::temp_dir().join(format!("serde_hyprlang_glob_{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("conf.d")).unwrap();
+    std::fs::write(dir.join("conf.d/b.conf"), "bind = SUPER, B, second\n").unwrap();
+    std::fs::write(dir.join("conf.d/a.conf"), "bind = SUPER, A, first\n").unwrap();
+
+    let main = dir.join("hyprland.conf");
+    let mut de = serde_hyprlang::Deserializer::from_str("source = ./conf.d/?.conf\n")
+        .file_name(&main.display().to_string())
+        .resolver(serde_hyprlang::FileSystem);
+    let res = Binds::deserialize(&mut de);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let expect = Binds {
+        bind: vec![
+            ("SUPER".to_string(), "A".to_string(), "first".to_string()),
+            ("SUPER".to_string(), "B".to_string(), "second".to_string()),
+        ]
+    };
+
+    assert_eq!(res, Ok(expect), "Failed to include a glob from the filesystem");
+}
@@ -1,3 +1,5 @@
+#![allow(clippy::needless_borrow)]
+
 use serde_hyprlang::Vec2;
 
 #[test]
@@ -22,3 +24,218 @@ fn vec2_deserialize() {
 
     assert_eq!(res, Ok(expect), "Failed to decode 2D vector correctly");
 }
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+struct Scalars {
+    flags: Vec<(bool, bool)>,
+    color: u32,
+    offset: i8,
+    scale: f32,
+}
+
+#[test]
+fn scalar_deserialize() {
+    let t =
+"flags = true, FALSE
+flags = yes, no
+flags = on, Off
+flags = 2, 0x0
+color = 0xFF33ccff
+offset = -0x10
+scale = 2
+";
+    let res = serde_hyprlang::from_str(t);
+
+    let expect = Scalars {
+        flags: vec![(true, false), (true, false), (true, false), (true, false)],
+        color: 0xff33ccff,
+        offset: -16,
+        scale: 2.0,
+    };
+
+    assert_eq!(res, Ok(expect), "Failed to decode hyprlang scalars");
+
+    let error = |t: &str| serde_hyprlang::from_str::<Scalars>(t).map_err(|e| e.to_string());
+
+    let t = "flags = maybe, no\ncolor = 1\noffset = 1\nscale = 1\n";
+    assert_eq!(error(t), Err("1:9: expected a bool, found `maybe`: hyprlang booleans are `true`, `false`, `yes`, `no`, `on`, `off` or a number (in `flags`)".to_string()), "Failed to reject an invalid bool");
+
+    let t = "flags = on, off\ncolor = -1\noffset = 1\nscale = 1\n";
+    assert_eq!(error(t), Err("2:9: expected an unsigned integer, found `-1`: out of range, it has to be between 0 and 4294967295 (in `color`)".to_string()), "Failed to reject a negative unsigned integer");
+
+    let t = "flags = on, off\ncolor = 1\noffset = 0x80\nscale = 1\n";
+    assert_eq!(error(t), Err("3:10: expected an integer, found `0x80`: out of range, it has to be between -128 and 127 (in `offset`)".to_string()), "Failed to reject an overflowing integer");
+
+    let t = "flags = on, off\ncolor = 1\noffset = 99999999999999999999999999999999999999999\nscale = 1\n";
+    assert_eq!(error(t), Err("3:10: expected an integer, found `99999999999999999999999999999999999999999`: out of range, it has to be between -128 and 127 (in `offset`)".to_string()), "Failed to reject a huge integer");
+
+    let t = "flags = on, off\ncolor = 1\noffset = 1\nscale = 1e39\n";
+    assert_eq!(error(t), Err("4:9: expected a float, found `1e39`: out of range, it has to be between -3.4028234663852886e38 and 3.4028234663852886e38 (in `scale`)".to_string()), "Failed to reject an overflowing float");
+}
+
+#[test]
+fn bool_style_serialize() {
+    let t = Scalars { flags: vec![(true, false)], color: 1, offset: -1, scale: 0.5 };
+
+    let style = |style| serde_hyprlang::to_string_with(&t, serde_hyprlang::Serializer::new().bool_style(style)).map(|text| text.lines().next().map(str::to_string));
+
+    assert_eq!(style(serde_hyprlang::BoolStyle::TrueFalse), Ok(Some("flags = true, false".to_string())), "Failed to write true/false");
+    assert_eq!(style(serde_hyprlang::BoolStyle::YesNo), Ok(Some("flags = yes, no".to_string())), "Failed to write yes/no");
+    assert_eq!(style(serde_hyprlang::BoolStyle::OnOff), Ok(Some("flags = on, off".to_string())), "Failed to write on/off");
+    assert_eq!(style(serde_hyprlang::BoolStyle::Numeric), Ok(Some("flags = 1, 0".to_string())), "Failed to write 1/0");
+
+    let text = serde_hyprlang::to_string_with(&t, serde_hyprlang::Serializer::new().bool_style(serde_hyprlang::BoolStyle::OnOff)).unwrap();
+    assert_eq!(serde_hyprlang::from_str(&text), Ok(t), "Failed to roundtrip on/off");
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+struct Colors {
+    active: serde_hyprlang::Color,
+    inactive: serde_hyprlang::Color,
+    shadow: serde_hyprlang::Color,
+    border: serde_hyprlang::Gradient,
+    group: serde_hyprlang::Gradient,
+}
+
+#[test]
+fn color_roundtrip() {
+    use serde_hyprlang::{Color, Gradient};
+
+    let t =
+"active = rgba(33ccffee)
+inactive = rgb(255, 0, 128)
+shadow = 0x80000000
+border = rgba(33ccffee) rgba(0, 255, 153, 0.5) 45deg
+group = rgb(595959)
+";
+    let res = serde_hyprlang::from_str(t);
+
+    let expect = Colors {
+        active: Color { r: 0x33, g: 0xcc, b: 0xff, a: 0xee },
+        inactive: Color { r: 255, g: 0, b: 128, a: 255 },
+        shadow: Color::from_argb(0x80000000),
+        border: Gradient { colors: vec![Color::from_argb(0xee33ccff), Color { r: 0, g: 255, b: 153, a: 128 }], angle: 45.0 },
+        group: Gradient { colors: vec![Color::from_argb(0xff595959)], angle: 0.0 },
+    };
+
+    assert_eq!(res, Ok(expect.clone()), "Failed to decode colors and gradients");
+
+    let text =
+"active = rgba(33ccffee)
+inactive = rgba(ff0080ff)
+shadow = rgba(00000080)
+border = rgba(33ccffee) rgba(00ff9980) 45deg
+group = rgba(595959ff)
+".to_string();
+
+    assert_eq!(serde_hyprlang::to_string(&expect), Ok(text), "Failed to encode colors and gradients");
+
+    let error = |t: &str| serde_hyprlang::from_str::<Color>(t).map_err(|e| e.to_string());
+    assert_eq!(error("rgba(33ccff)"), Err("expected a color, found `rgba(33ccff)`: expected 8 hex digits, found `33ccff`".to_string()), "Failed to report a short color");
+    assert_eq!(error("rgb(0, 256, 0)"), Err("expected a color, found `rgb(0, 256, 0)`: `256` is not a channel between 0 and 255".to_string()), "Failed to report an invalid channel");
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+struct Dynamic {
+    values: std::collections::BTreeMap<String, serde_hyprlang::Value>,
+    workspace: serde_hyprlang::Inferred,
+    monitor: (String, serde_hyprlang::Inferred),
+}
+
+#[test]
+fn infer_deserialize() {
+    use serde_hyprlang::{Color, Gradient, Inferred, Value};
+
+    let t =
+"values {
+    bool = on
+    int = -0x10
+    float = 1.5e3
+    vec = 0.5 -2
+    color = rgb(33ccff)
+    gradient = rgb(000000) rgb(ffffff) 90deg
+    string = kitty --single-instance
+    nan = NaN
+}
+workspace = 07
+monitor = DP-1, 1920 1080
+";
+    let res = serde_hyprlang::from_str(t);
+
+    let values = [
+        ("bool", Value::Bool(true)),
+        ("int", Value::Int(-16)),
+        ("float", Value::Float(1500.0)),
+        ("vec", Value::Vec2(Vec2(0.5, -2.0))),
+        ("color", Value::Color(Color::from_argb(0xff33ccff))),
+        ("gradient", Value::Gradient(Gradient { colors: vec![Color::from_argb(0xff000000), Color::from_argb(0xffffffff)], angle: 90.0 })),
+        ("string", Value::String("kitty --single-instance".to_string())),
+        ("nan", Value::String("NaN".to_string())),
+    ];
+    let expect = Dynamic {
+        values: values.into_iter().map(|(key, value)| (key.to_string(), value)).collect(),
+        workspace: Inferred { value: Value::Int(7), text: "07".to_string() },
+        monitor: ("DP-1".to_string(), Inferred { value: Value::Vec2(Vec2(1920.0, 1080.0)), text: "1920 1080".to_string() }),
+    };
+
+    assert_eq!(res, Ok(expect.clone()), "Failed to infer the types of values");
+
+    let res = serde_hyprlang::to_string(&expect);
+
+    // The inferred values are written as their text, the others by their type
+    let text =
+"values {
+    bool = true
+    color = rgba(33ccffff)
+    float = 1500
+    gradient = rgba(000000ff) rgba(ffffffff) 90deg
+    int = -16
+    nan = NaN
+    string = kitty --single-instance
+    vec = 0.5 -2
+}
+
+workspace = 07
+monitor = DP-1, 1920 1080
+".to_string();
+
+    assert_eq!(res, Ok(text), "Failed to encode inferred values");
+
+    assert_eq!(serde_hyprlang::from_str::<Value>("yes"), Ok(Value::Bool(true)), "Failed to infer a value at the root");
+    assert_eq!(serde_hyprlang::from_str::<(String, Vec2)>("pos, 1 2"), Ok(("pos".to_string(), Vec2(1.0, 2.0))), "Failed to read a vec2 from an element");
+    assert_eq!(Value::infer("1 2 3"), Value::String("1 2 3".to_string()), "Failed to keep numbers separated by spaces as text");
+    assert_eq!(Value::infer("0xff000000 0xffffffff"), Value::String("0xff000000 0xffffffff".to_string()), "Failed to keep integers separated by spaces as text");
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+struct FlatDynamic {
+    #[serde(flatten)]
+    values: std::collections::BTreeMap<String, serde_hyprlang::Inferred>,
+}
+
+#[test]
+fn infer_flatten_deserialize() {
+    use serde_hyprlang::{Color, Gradient, Inferred, Value};
+
+    let t =
+"vec = 0.5 -2
+color = rgb(33ccff)
+gradient = rgb(000000) rgb(ffffff) 90deg
+hex = 0x1f
+";
+    let res = serde_hyprlang::from_str(t);
+
+    let values = [
+        ("vec", Inferred { value: Value::Vec2(Vec2(0.5, -2.0)), text: "0.5 -2".to_string() }),
+        ("color", Inferred { value: Value::Color(Color::from_argb(0xff33ccff)), text: "rgb(33ccff)".to_string() }),
+        ("gradient", Inferred {
+            value: Value::Gradient(Gradient { colors: vec![Color::from_argb(0xff000000), Color::from_argb(0xffffffff)], angle: 90.0 }),
+            text: "rgb(000000) rgb(ffffff) 90deg".to_string()
+        }),
+        // serde keeps only the value of numbers
+        ("hex", Inferred { value: Value::Int(31), text: "31".to_string() }),
+    ];
+    let expect = FlatDynamic { values: values.into_iter().map(|(key, value)| (key.to_string(), value)).collect() };
+
+    assert_eq!(res, Ok(expect), "Failed to infer flattened values with their text");
+}
@@ -0,0 +1,152 @@
+use serde::Deserialize;
+use serde_hyprlang::{Error, SyntaxKind};
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Binds {
+    bind: Vec<(String, String, String, String)>
+}
+
+#[test]
+fn variable_expansion() {
+    let t =
+"$mainMod = SUPER
+$terminal = kitty --single-instance
+$shifted = $mainMod SHIFT
+
+bind = $mainMod, Q, exec, $terminal
+bind = $shifted, Q, killactive, 
+";
+    let mut de = serde_hyprlang::Deserializer::from_str(t);
+    let res = Binds::deserialize(&mut de);
+
+    let expect = Binds {
+        bind: vec![
+            ("SUPER".to_string(), "Q".to_string(), "exec".to_string(), "kitty --single-instance".to_string()),
+            ("SUPER SHIFT".to_string(), "Q".to_string(), "killactive".to_string(), "".to_string()),
+        ]
+    };
+
+    assert_eq!(res, Ok(expect), "Failed to expand variables");
+    assert_eq!(de.variables().get("shifted").map(String::as_str), Some("SUPER SHIFT"), "Failed to provide the expanded variables");
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Single {
+    value: String
+}
+
+#[test]
+fn variable_errors() {
+    let res: serde_hyprlang::Result<Single> = serde_hyprlang::from_str("value = ^(kitty)$\n");
+    assert_eq!(res, Ok(Single { value: "^(kitty)$".to_string() }), "Failed to keep a lone $");
+
+    let res = serde_hyprlang::from_str::<Single>("\nvalue = $nope\n").map_err(|e| e.to_string());
+    assert_eq!(res, Err("2:9: variable `$nope` is not defined (in `value`)".to_string()), "Failed to report an undefined variable");
+
+    let res = serde_hyprlang::from_str::<Single>("$a = $b\n$b = x $a\nvalue = $a\n");
+    let err = res.expect_err("Failed to detect a cycle");
+    assert_eq!(err.inner(), &Error::Syntax(SyntaxKind::RecursiveVariable, "variable `$a` refers to itself: $a -> $b -> $a".to_string()), "Failed to detect a cycle");
+    assert_eq!(err.location().map(|l| (l.line, l.column, l.span.clone())), Some((2, 8, 15..17)), "Failed to locate the cycle");
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, Deserialize)]
+struct RefBinds {
+    bind: Vec<(serde_hyprlang::VarRef<String>, String, String, serde_hyprlang::VarRef<String>)>
+}
+
+#[test]
+fn var_ref_serialize() {
+    use serde_hyprlang::VarRef;
+
+    let t = RefBinds {
+        bind: vec![
+            (VarRef::var("mainMod"), "Q".to_string(), "exec".to_string(), VarRef::var("terminal")),
+            (VarRef::var("mainMod"), "E".to_string(), "exec".to_string(), VarRef::Value("nautilus".to_string())),
+        ]
+    };
+    let ser = serde_hyprlang::Serializer::new()
+        .variable("mainMod", "SUPER")
+        .variable("terminal", "kitty");
+    let res = serde_hyprlang::to_string_with(&t, ser);
+
+    let expect =
+"$mainMod = SUPER
+$terminal = kitty
+
+bind = $mainMod, Q, exec, $terminal
+bind = $mainMod, E, exec, nautilus
+
+".to_string();
+
+    assert_eq!(res, Ok(expect.clone()), "Failed to encode variable references");
+
+    let res = serde_hyprlang::from_str(&expect);
+    assert_eq!(res, Ok(t), "Failed to keep variable references");
+
+    let res = RefBinds::deserialize(&mut serde_hyprlang::Deserializer::from_str(&expect).resolve_var_refs());
+    let resolved = RefBinds {
+        bind: vec![
+            (VarRef::Value("SUPER".to_string()), "Q".to_string(), "exec".to_string(), VarRef::Value("kitty".to_string())),
+            (VarRef::Value("SUPER".to_string()), "E".to_string(), "exec".to_string(), VarRef::Value("nautilus".to_string())),
+        ]
+    };
+    assert_eq!(res, Ok(resolved), "Failed to resolve variable references");
+}
+
+#[test]
+fn var_ref_serialize_errors() {
+    use serde_hyprlang::VarRef;
+
+    let res = serde_hyprlang::to_string(&VarRef::<String>::var("a b")).map_err(|e| e.to_string());
+    assert_eq!(res, Err("`$a b` can not be written as a key".to_string()), "Failed to reject a variable name with a space");
+
+    let res = serde_hyprlang::to_string(&VarRef::<String>::var("")).map_err(|e| e.to_string());
+    assert_eq!(res, Err("`$` can not be written as a key".to_string()), "Failed to reject an empty variable name");
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Environment {
+    env: Vec<(String, String)>,
+    value: String
+}
+
+#[test]
+fn environment_expansion() {
+    let environment = [
+        ("HOME".to_string(), "/home/vaxry".to_string()),
+        ("XDG_CONFIG_HOME".to_string(), "/home/vaxry/.config".to_string()),
+        ("value".to_string(), "from the environment".to_string()),
+    ].into_iter().collect::<std::collections::BTreeMap<_, _>>();
+
+    let t =
+"$value = from the text
+env = XCURSOR_PATH, $HOME/.icons
+env = HYPR, $XDG_CONFIG_HOME/hypr
+value = $value
+";
+    let res = Environment::deserialize(&mut serde_hyprlang::Deserializer::from_str(t).environment(environment.clone()));
+
+    let expect = Environment {
+        env: vec![
+            ("XCURSOR_PATH".to_string(), "/home/vaxry/.icons".to_string()),
+            ("HYPR".to_string(), "/home/vaxry/.config/hypr".to_string()),
+        ],
+        value: "from the text".to_string()
+    };
+
+    assert_eq!(res, Ok(expect), "Failed to expand environment variables");
+
+    let t = "env = PATH, $HOME/bin:$PATH\nvalue = x\n";
+    let res = Environment::deserialize(&mut serde_hyprlang::Deserializer::from_str(t).environment(environment.clone()))
+        .map_err(|e| e.to_string());
+    assert_eq!(res, Err("1:23: variable `$PATH` is not defined, and not set in the environment (in `env`)".to_string()), "Failed to report an unset environment variable");
+
+    let res = Environment::deserialize(&mut serde_hyprlang::Deserializer::from_str(t).environment(environment).keep_unresolved_environment());
+    assert_eq!(res.map(|e| e.env), Ok(vec![("PATH".to_string(), "/home/vaxry/bin:$PATH".to_string())]), "Failed to keep an unset environment variable");
+
+    let res = serde_hyprlang::from_str::<Environment>("env = A, $HOME\nvalue = x\n").map_err(|e| e.to_string());
+    assert_eq!(res, Err("1:10: variable `$HOME` is not defined (in `env`)".to_string()), "Failed to ignore the environment by default");
+
+    let res = Environment::deserialize(&mut serde_hyprlang::Deserializer::from_str("env = A, $HOME\nvalue = x\n").keep_unresolved_environment());
+    assert_eq!(res.map(|e| e.env), Ok(vec![("A".to_string(), "$HOME".to_string())]), "Failed to keep an undefined variable without an environment");
+}